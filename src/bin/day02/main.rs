@@ -1,7 +1,7 @@
 mod error;
 mod models;
 
-use advent_of_code_2024::{init, load_day_input};
+use advent_of_code_2024::{has_cli_flag, init, load_day_input};
 use eyre::Context;
 use models::{make_diff_rule, make_sorted_rule, SafetyRules, UnusualData};
 use tracing::info;
@@ -20,6 +20,11 @@ fn main() -> eyre::Result<()> {
     let part_2_res = part2(&data)?;
     info!("Part 2 solution: {part_2_res}");
 
+    if has_cli_flag("--diagnose") {
+        info!("diagnosing unsafe reports...");
+        diagnose(&data)?;
+    }
+
     Ok(())
 }
 
@@ -44,3 +49,17 @@ fn part2(data: &str) -> eyre::Result<usize> {
 
     Ok(safe_count)
 }
+
+/// Lists every unsafe report together with the transition that broke it
+fn diagnose(data: &str) -> eyre::Result<()> {
+    let data = data
+        .parse::<UnusualData>()
+        .wrap_err("failed to parse data")?;
+    let maker = || SafetyRules::new(vec![make_sorted_rule(), make_diff_rule()]);
+
+    for diagnostic in data.diagnose(maker) {
+        info!("{diagnostic}");
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 use derive_more::derive::Display;
 use itertools::Itertools;
@@ -39,15 +39,39 @@ impl Report {
         Self { levels }
     }
 
-    fn is_safe(&self, mut rules: SafetyRules, skip_level_idx: Option<usize>) -> bool {
+    fn is_safe(&self, rules: SafetyRules, skip_level_idx: Option<usize>) -> bool {
+        self.first_violation(rules, skip_level_idx).is_none()
+    }
+
+    /// Finds first pair of levels that breaks one of the `rules`, `None` if report is safe
+    fn first_violation(
+        &self,
+        mut rules: SafetyRules,
+        skip_level_idx: Option<usize>,
+    ) -> Option<Violation> {
         self.levels
             .iter()
             .enumerate()
             // if `skip_level_idx` given, that means we ignore nth element completely when checking
             // the rules
-            .filter(|(idx, _)| skip_level_idx.map_or(true, |skip| skip != *idx))
+            .filter(|(idx, _)| skip_level_idx != Some(*idx))
             .tuple_windows()
-            .all(|((_, lhs), (_, rhs))| rules.rules.iter_mut().all(|rule| rule(*lhs, *rhs)))
+            .find_map(|((lhs_idx, lhs), (rhs_idx, rhs))| {
+                rules
+                    .rules
+                    .iter_mut()
+                    .find_map(|rule| (!(rule.check)(*lhs, *rhs)).then_some(rule.kind))
+                    .map(|rule| Violation {
+                        lhs_idx,
+                        rhs_idx,
+                        rule,
+                    })
+            })
+    }
+
+    /// Index of level which, when removed, makes report safe
+    fn dampened_level(&self, rules_maker: impl Fn() -> SafetyRules) -> Option<usize> {
+        (0..self.levels.len()).find(|i| self.is_safe(rules_maker(), Some(*i)))
     }
 }
 
@@ -89,13 +113,8 @@ impl UnusualData {
         let mut safe_count = 0;
         for report in self.reports.iter() {
             if !report.is_safe(rules_maker(), None) {
-                if problem_dampener {
-                    for i in 0..report.levels.len() {
-                        if report.is_safe(rules_maker(), Some(i)) {
-                            safe_count += 1;
-                            break;
-                        }
-                    }
+                if problem_dampener && report.dampened_level(&rules_maker).is_some() {
+                    safe_count += 1;
                 }
             } else {
                 safe_count += 1;
@@ -104,6 +123,27 @@ impl UnusualData {
 
         safe_count
     }
+
+    /// Explains why each unsafe report failed the `rules`. Safe reports are skipped.
+    pub(crate) fn diagnose(
+        &self,
+        rules_maker: impl Fn() -> SafetyRules,
+    ) -> Vec<ReportDiagnostic<'_>> {
+        self.reports
+            .iter()
+            .enumerate()
+            .filter_map(|(report_idx, report)| {
+                let violation = report.first_violation(rules_maker(), None)?;
+
+                Some(ReportDiagnostic {
+                    report_idx,
+                    report,
+                    violation,
+                    dampened_level: report.dampened_level(&rules_maker),
+                })
+            })
+            .collect()
+    }
 }
 
 impl FromStr for UnusualData {
@@ -120,7 +160,20 @@ impl FromStr for UnusualData {
     }
 }
 
-type Rule = Box<dyn FnMut(Level, Level) -> bool>;
+/// Identifies which safety rule rejected a pair of levels
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub(crate) enum RuleKind {
+    #[display("levels not strictly increasing or decreasing")]
+    Sorted,
+    #[display("adjacent levels differ by less than 1 or more than 3")]
+    Diff,
+}
+
+pub(crate) struct Rule {
+    kind: RuleKind,
+    check: Box<dyn FnMut(Level, Level) -> bool>,
+}
+
 pub(crate) struct SafetyRules {
     rules: Vec<Rule>,
 }
@@ -152,7 +205,10 @@ pub(crate) fn make_sorted_rule() -> Rule {
         }
     };
 
-    Box::new(rule)
+    Rule {
+        kind: RuleKind::Sorted,
+        check: Box::new(rule),
+    }
 }
 
 pub(crate) fn make_diff_rule() -> Rule {
@@ -161,5 +217,105 @@ pub(crate) fn make_diff_rule() -> Rule {
         (1..=3).contains(&diff)
     };
 
-    Box::new(rule)
+    Rule {
+        kind: RuleKind::Diff,
+        check: Box::new(rule),
+    }
+}
+
+/// First offending pair of levels in a report. Indices point into the original report, so
+/// they are not necessarily adjacent when a level was skipped.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Violation {
+    pub(crate) lhs_idx: usize,
+    pub(crate) rhs_idx: usize,
+    pub(crate) rule: RuleKind,
+}
+
+pub(crate) struct ReportDiagnostic<'a> {
+    pub(crate) report_idx: usize,
+    pub(crate) report: &'a Report,
+    pub(crate) violation: Violation,
+    /// Level whose removal makes the report safe, `None` if dampener can not rescue it
+    pub(crate) dampened_level: Option<usize>,
+}
+
+impl Display for ReportDiagnostic<'_> {
+    /// Renders report levels with offending transition wrapped in brackets, e.g.
+    /// `#1: 1 [2 7] 8 9 -- adjacent levels differ by less than 1 or more than 3; ...`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Violation {
+            lhs_idx,
+            rhs_idx,
+            rule,
+        } = self.violation;
+
+        write!(f, "#{}:", self.report_idx)?;
+        for (idx, level) in self.report.levels.iter().enumerate() {
+            match idx {
+                _ if idx == lhs_idx => write!(f, " [{level}")?,
+                _ if idx == rhs_idx => write!(f, " {level}]")?,
+                _ => write!(f, " {level}")?,
+            }
+        }
+        write!(f, " -- {rule}; ")?;
+
+        match self.dampened_level {
+            Some(idx) => write!(
+                f,
+                "dampener rescues it by removing level {} at index {idx}",
+                self.report.levels[idx]
+            ),
+            None => write!(f, "dampener can not rescue it"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> SafetyRules {
+        SafetyRules::new(vec![make_sorted_rule(), make_diff_rule()])
+    }
+
+    #[test]
+    fn diagnose_reports_first_violation() {
+        let data = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9"
+            .parse::<UnusualData>()
+            .unwrap();
+
+        let diagnostics = data.diagnose(rules);
+        let summary = diagnostics
+            .iter()
+            .map(|d| (d.report_idx, d.violation, d.dampened_level))
+            .collect::<Vec<_>>();
+
+        let violation = |lhs_idx, rhs_idx, rule| Violation {
+            lhs_idx,
+            rhs_idx,
+            rule,
+        };
+        assert_eq!(
+            vec![
+                (1, violation(1, 2, RuleKind::Diff), None),
+                (2, violation(2, 3, RuleKind::Diff), None),
+                (3, violation(1, 2, RuleKind::Sorted), Some(1)),
+                (4, violation(2, 3, RuleKind::Diff), Some(2)),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn diagnostic_highlights_offending_transition() {
+        let data = "1 2 7 8 9".parse::<UnusualData>().unwrap();
+
+        let diagnostics = data.diagnose(rules);
+
+        assert_eq!(
+            "#0: 1 [2 7] 8 9 -- adjacent levels differ by less than 1 or more than 3; dampener can not rescue it",
+            diagnostics[0].to_string()
+        );
+    }
 }
@@ -1,7 +1,14 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Day03Error {
     #[error("could not parse '{input}' into mul instruction: {error_msg}")]
     MulInstrParseError { input: String, error_msg: String },
+
+    #[error("instruction '{instr}' at offset {offset} overflowed accumulator")]
+    MulOverflowError { instr: String, offset: usize },
+
+    #[error("failed to read memory at offset {offset}: {error_msg}")]
+    MemoryReadError { offset: usize, error_msg: String },
 }
@@ -0,0 +1,212 @@
+use crate::{
+    error::Day03Error,
+    models::{MulInstr, MulParse},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Mul(MulInstr),
+    Do,
+    Dont,
+    /// Run of corrupted bytes which do not form any known instruction
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    /// Byte offset of token start in lexed input
+    pub(crate) offset: usize,
+    /// Length of token in bytes
+    pub(crate) len: usize,
+}
+
+/// Puzzle allows `mul` operands of 1-3 digits, longer numbers make instruction corrupted
+pub(crate) const MAX_OPERAND_DIGITS: usize = 3;
+
+/// Recognises known instructions at start of byte slice. Instructions are pure ASCII, so
+/// matching at any byte offset of UTF-8 input never splits a character.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InstrMatcher {
+    max_operand_digits: usize,
+}
+
+impl InstrMatcher {
+    const ENABLE_INSTR: &[u8] = b"do()";
+    const DISABLE_INSTR: &[u8] = b"don't()";
+    const MUL_INSTR_PREFIX: &[u8] = b"mul(";
+
+    pub(crate) fn new(max_operand_digits: usize) -> Self {
+        Self { max_operand_digits }
+    }
+
+    /// Number of bytes that must be visible after match position for `match_instr` result to be
+    /// final, i.e. not change once more input arrives
+    pub(crate) fn window(&self) -> usize {
+        // prefix, two operands with one extra digit to detect too long operand, `,` and `)`
+        let mul_window = Self::MUL_INSTR_PREFIX.len() + 2 * (self.max_operand_digits + 1) + 2;
+        mul_window.max(Self::DISABLE_INSTR.len())
+    }
+
+    /// Tries to match known instruction at start of `rest`, returns its kind and length in bytes
+    pub(crate) fn match_instr(
+        &self,
+        rest: &[u8],
+    ) -> Result<Option<(TokenKind, usize)>, Day03Error> {
+        if rest.starts_with(Self::ENABLE_INSTR) {
+            return Ok(Some((TokenKind::Do, Self::ENABLE_INSTR.len())));
+        }
+
+        if rest.starts_with(Self::DISABLE_INSTR) {
+            return Ok(Some((TokenKind::Dont, Self::DISABLE_INSTR.len())));
+        }
+
+        let Some(len) = self.match_mul(rest) else {
+            return Ok(None);
+        };
+        let instr = std::str::from_utf8(&rest[..len])
+            .expect("matched mul instruction is ascii")
+            .parse::<MulParse>()?;
+
+        match instr {
+            MulParse::Instr(instr) => Ok(Some((TokenKind::Mul(instr), len))),
+            MulParse::OperandOverflow => Ok(None),
+        }
+    }
+
+    /// Length of `mul(<digits>,<digits>)` at start of `rest` if present
+    fn match_mul(&self, rest: &[u8]) -> Option<usize> {
+        let operands = rest.strip_prefix(Self::MUL_INSTR_PREFIX)?;
+        let valid_len = |len| (1..=self.max_operand_digits).contains(&len);
+
+        let lhs_len = operands.iter().take_while(|b| b.is_ascii_digit()).count();
+        if !valid_len(lhs_len) || operands.get(lhs_len) != Some(&b',') {
+            return None;
+        }
+
+        let rhs = &operands[lhs_len + 1..];
+        let rhs_len = rhs.iter().take_while(|b| b.is_ascii_digit()).count();
+        if !valid_len(rhs_len) || rhs.get(rhs_len) != Some(&b')') {
+            return None;
+        }
+
+        Some(Self::MUL_INSTR_PREFIX.len() + lhs_len + 1 + rhs_len + 1)
+    }
+}
+
+impl Default for InstrMatcher {
+    fn default() -> Self {
+        Self::new(MAX_OPERAND_DIGITS)
+    }
+}
+
+/// Splits corrupted memory into stream of tokens. Every byte of input belongs to exactly one
+/// token, garbage between instructions is reported as single `TokenKind::Unknown` token.
+pub(crate) struct Lexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    matcher: InstrMatcher,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            pos: 0,
+            matcher: InstrMatcher::default(),
+        }
+    }
+
+    /// Starts lexing at byte offset `pos`, token offsets stay relative to start of input
+    pub(crate) fn starting_at(mut self, pos: usize) -> Self {
+        self.pos = pos;
+        self
+    }
+
+    pub(crate) fn with_max_operand_digits(mut self, max_operand_digits: usize) -> Self {
+        self.matcher = InstrMatcher::new(max_operand_digits);
+        self
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, Day03Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+
+        for offset in start..self.input.len() {
+            let matched = match self.matcher.match_instr(&self.input[offset..]) {
+                Ok(matched) => matched,
+                Err(e) => return Some(Err(e)),
+            };
+
+            let Some((kind, len)) = matched else {
+                continue;
+            };
+
+            // garbage before instruction is emitted first, instruction is lexed again on next call
+            if offset > start {
+                self.pos = offset;
+                return Some(Ok(Token {
+                    kind: TokenKind::Unknown,
+                    offset: start,
+                    len: offset - start,
+                }));
+            }
+
+            self.pos = offset + len;
+            return Some(Ok(Token { kind, offset, len }));
+        }
+
+        if start < self.input.len() {
+            self.pos = self.input.len();
+            return Some(Ok(Token {
+                kind: TokenKind::Unknown,
+                offset: start,
+                len: self.input.len() - start,
+            }));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lexes_instructions_with_offsets() {
+        let tokens = Lexer::new("xmul(2,4)&do()don't()mul[3,7]")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let summary = tokens
+            .iter()
+            .map(|t| (&t.kind, t.offset, t.len))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (&TokenKind::Unknown, 0, 1),
+                (&TokenKind::Mul(MulInstr::new(2, 4)), 1, 8),
+                (&TokenKind::Unknown, 9, 1),
+                (&TokenKind::Do, 10, 4),
+                (&TokenKind::Dont, 14, 7),
+                (&TokenKind::Unknown, 21, 8),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn malformed_mul_is_unknown() {
+        let tokens = Lexer::new("mul(4*mul(6,9!?(12,34)mul ( 2 , 4 )")
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(1, tokens.len());
+        assert_eq!(TokenKind::Unknown, tokens[0].kind);
+    }
+}
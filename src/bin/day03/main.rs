@@ -1,40 +1,111 @@
 mod error;
+mod lexer;
 mod models;
+mod stream;
+mod vm;
 
-use advent_of_code_2024::{init, load_day_input};
+use advent_of_code_2024::{has_cli_flag, init, load_day_input, open_day_input};
 use eyre::Context;
-use models::{ConditionalMemory, Memory};
+use lexer::MAX_OPERAND_DIGITS;
+use models::{run_stream, ConditionalMemory, Memory};
 use tracing::info;
+use vm::Arithmetic;
 
 fn main() -> eyre::Result<()> {
     init();
 
+    let arithmetic = arithmetic_from_args();
+    info!("using {arithmetic:?} arithmetic");
+
+    if has_cli_flag("--stream") {
+        return solve_streaming(arithmetic);
+    }
+
     info!("loading data...");
     let data = load_day_input("day03.txt")?;
 
     info!("solving part 1...");
-    let part_1_res = part1(&data)?;
+    let part_1_res = part1(&data, arithmetic)?;
     info!("Part 1 solution: {part_1_res}");
 
     info!("solving part 2...");
-    let part_2_res = part2(&data)?;
+    let part_2_res = part2(&data, arithmetic)?;
+    info!("Part 2 solution: {part_2_res}");
+
+    Ok(())
+}
+
+/// Reads input in fixed size chunks instead of loading it whole, once per part. `StreamLexer`
+/// buffers the file itself.
+fn solve_streaming(arithmetic: Arithmetic) -> eyre::Result<()> {
+    info!("streaming part 1...");
+    let input = open_day_input("day03.txt")?;
+    let part_1_res = run_stream(input, false, arithmetic, MAX_OPERAND_DIGITS)
+        .wrap_err("failed to execute streamed instructions")?;
+    info!("Part 1 solution: {part_1_res}");
+
+    info!("streaming part 2...");
+    let input = open_day_input("day03.txt")?;
+    let part_2_res = run_stream(input, true, arithmetic, MAX_OPERAND_DIGITS)
+        .wrap_err("failed to execute streamed instructions")?;
     info!("Part 2 solution: {part_2_res}");
 
     Ok(())
 }
 
-fn part1(data: &str) -> eyre::Result<u64> {
+/// `--saturating` clamps results at `u64::MAX`, `--wide` accumulates in `u128`
+fn arithmetic_from_args() -> Arithmetic {
+    if has_cli_flag("--wide") {
+        Arithmetic::Wide
+    } else if has_cli_flag("--saturating") {
+        Arithmetic::Saturating
+    } else {
+        Arithmetic::Checked
+    }
+}
+
+fn part1(data: &str, arithmetic: Arithmetic) -> eyre::Result<u128> {
     let memory = data
         .parse::<Memory>()
         .wrap_err("failed to parse instructions from corrupted memory")?;
 
-    Ok(memory.run())
+    memory
+        .run(arithmetic)
+        .wrap_err("failed to execute instructions")
 }
 
-fn part2(data: &str) -> eyre::Result<u64> {
+fn part2(data: &str, arithmetic: Arithmetic) -> eyre::Result<u128> {
     let memory = data
         .parse::<ConditionalMemory>()
         .wrap_err("failed to parse instructions from corrupted memory")?;
 
-    Ok(memory.run())
+    memory
+        .run(arithmetic)
+        .wrap_err("failed to execute instructions")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_1: &str =
+        r"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const SAMPLE_2: &str =
+        r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn part_1_sample_data() {
+        let res =
+            part1(SAMPLE_1, Arithmetic::default()).expect("part 1 not to error on sample data");
+
+        assert_eq!(161, res);
+    }
+
+    #[test]
+    fn part_2_sample_data() {
+        let res =
+            part2(SAMPLE_2, Arithmetic::default()).expect("part 2 not to error on sample data");
+
+        assert_eq!(48, res);
+    }
 }
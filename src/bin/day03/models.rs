@@ -1,26 +1,60 @@
-use std::{str::FromStr, sync::OnceLock};
+use std::{io::Read, num::IntErrorKind, str::FromStr};
 
-use regex::Regex;
+use derive_more::derive::Display;
 use tracing::info;
 
-use crate::error::Day03Error;
+use crate::{
+    error::Day03Error,
+    lexer::{Lexer, TokenKind, MAX_OPERAND_DIGITS},
+    stream::StreamLexer,
+    vm::{Arithmetic, Instr, Vm},
+};
 
+#[derive(Debug, PartialEq, Eq, Display)]
+#[display("mul({lhs},{rhs})")]
 pub(crate) struct MulInstr {
-    lhs: u64,
-    rhs: u64,
+    lhs: u128,
+    rhs: u128,
 }
 
 impl MulInstr {
-    fn new(lhs: u64, rhs: u64) -> Self {
+    pub(crate) fn new(lhs: u128, rhs: u128) -> Self {
         Self { lhs, rhs }
     }
 
-    fn exec(&self) -> u64 {
-        self.lhs * self.rhs
+    /// Adds product of operands to `acc` using given `arithmetic`, `None` on overflow
+    pub(crate) fn exec(&self, acc: u128, arithmetic: Arithmetic) -> Option<u128> {
+        match arithmetic {
+            Arithmetic::Checked => {
+                let acc = u64::try_from(acc).ok()?;
+                let lhs = u64::try_from(self.lhs).ok()?;
+                let rhs = u64::try_from(self.rhs).ok()?;
+                let product = lhs.checked_mul(rhs)?;
+                acc.checked_add(product).map(u128::from)
+            }
+            Arithmetic::Saturating => {
+                let clamp = |value: u128| u64::try_from(value).unwrap_or(u64::MAX);
+                let product = clamp(self.lhs).saturating_mul(clamp(self.rhs));
+                Some(clamp(acc).saturating_add(product).into())
+            }
+            Arithmetic::Wide => {
+                let product = self.lhs.checked_mul(self.rhs)?;
+                acc.checked_add(product)
+            }
+        }
     }
 }
 
-impl FromStr for MulInstr {
+/// Outcome of parsing `mul` instruction
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum MulParse {
+    Instr(MulInstr),
+    /// Operand does not fit into `u128`. There is no big integer backend, so such instruction
+    /// is treated as corrupted memory.
+    OperandOverflow,
+}
+
+impl FromStr for MulParse {
     type Err = Day03Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -40,11 +74,8 @@ impl FromStr for MulInstr {
             });
         };
 
-        let Ok(lhs) = lhs.parse::<u64>() else {
-            return Err(Day03Error::MulInstrParseError {
-                input: lhs.to_owned(),
-                error_msg: "could not parse lhs".to_owned(),
-            });
+        let Some(lhs) = parse_operand(lhs, "could not parse lhs")? else {
+            return Ok(MulParse::OperandOverflow);
         };
 
         let Some(rhs) = rest.strip_suffix(')') else {
@@ -54,28 +85,59 @@ impl FromStr for MulInstr {
             });
         };
 
-        let Ok(rhs) = rhs.parse::<u64>() else {
-            return Err(Day03Error::MulInstrParseError {
-                input: rhs.to_owned(),
-                error_msg: "could not parse rhs".to_owned(),
-            });
+        let Some(rhs) = parse_operand(rhs, "could not parse rhs")? else {
+            return Ok(MulParse::OperandOverflow);
         };
 
-        Ok(MulInstr::new(lhs, rhs))
+        Ok(MulParse::Instr(MulInstr::new(lhs, rhs)))
+    }
+}
+
+/// `None` if operand does not fit into `u128`
+fn parse_operand(operand: &str, error_msg: &str) -> Result<Option<u128>, Day03Error> {
+    match operand.parse::<u128>() {
+        Ok(operand) => Ok(Some(operand)),
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => Ok(None),
+        Err(_) => Err(Day03Error::MulInstrParseError {
+            input: operand.to_owned(),
+            error_msg: error_msg.to_owned(),
+        }),
     }
 }
 
 pub(crate) struct Memory {
-    instr: Vec<MulInstr>,
+    program: Vec<Instr>,
 }
 
 impl Memory {
-    fn new(instr: Vec<MulInstr>) -> Self {
-        Self { instr }
+    fn new(program: Vec<Instr>) -> Self {
+        Self { program }
+    }
+
+    /// Parses memory accepting `mul` operands with at most `max_operand_digits` digits
+    pub(crate) fn parse_with(s: &str, max_operand_digits: usize) -> Result<Self, Day03Error> {
+        Self::parse_region(s, 0, s.len(), max_operand_digits)
     }
 
-    pub(crate) fn run(&self) -> u64 {
-        self.instr.iter().map(|mul| mul.exec()).sum()
+    /// Parses `s[start..end]`, instruction offsets stay relative to start of `s`
+    fn parse_region(
+        s: &str,
+        start: usize,
+        end: usize,
+        max_operand_digits: usize,
+    ) -> Result<Self, Day03Error> {
+        let program = Lexer::new(&s[..end])
+            .starting_at(start)
+            .with_max_operand_digits(max_operand_digits)
+            .map(|token| token.map(Instr::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Memory::new(program))
+    }
+
+    /// Executes every `mul` instruction, `do()` and `don't()` are ignored
+    pub(crate) fn run(&self, arithmetic: Arithmetic) -> Result<u128, Day03Error> {
+        Vm::new(false, arithmetic).run(&self.program)
     }
 }
 
@@ -83,15 +145,7 @@ impl FromStr for Memory {
     type Err = Day03Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        static RE_CELL: OnceLock<Regex> = OnceLock::new();
-        let re = RE_CELL.get_or_init(|| Regex::new(r"mul\(\d+,\d+\)").unwrap());
-
-        let instrs = re
-            .captures_iter(s)
-            .map(|mul_str| mul_str.get(0).unwrap().as_str().parse::<MulInstr>())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(Memory::new(instrs))
+        Self::parse_with(s, MAX_OPERAND_DIGITS)
     }
 }
 
@@ -106,8 +160,23 @@ impl ConditionalMemory {
         }
     }
 
-    pub(crate) fn run(&self) -> u64 {
-        self.enabled_regions.iter().map(|mem| mem.run()).sum()
+    /// Parses memory accepting `mul` operands with at most `max_operand_digits` digits
+    pub(crate) fn parse_with(s: &str, max_operand_digits: usize) -> Result<Self, Day03Error> {
+        let memory_regions = enabled_regions(s)?
+            .into_iter()
+            .map(|(start, end)| Memory::parse_region(s, start, end, max_operand_digits))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ConditionalMemory::new(memory_regions))
+    }
+
+    pub(crate) fn run(&self, arithmetic: Arithmetic) -> Result<u128, Day03Error> {
+        let program = self
+            .enabled_regions
+            .iter()
+            .flat_map(|mem| mem.program.iter());
+
+        Vm::new(false, arithmetic).run(program)
     }
 }
 
@@ -115,52 +184,192 @@ impl FromStr for ConditionalMemory {
     type Err = Day03Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const ENABLE_INSTR: &str = "do()";
-        const DISABLE_INSTR: &str = "don't()";
+        Self::parse_with(s, MAX_OPERAND_DIGITS)
+    }
+}
 
-        let do_idxs = s
-            .match_indices(ENABLE_INSTR)
-            .map(|(start, slice)| start + slice.len())
-            .collect::<Vec<_>>();
+/// Executes instructions read from `reader` without loading whole memory, accepting `mul`
+/// operands with at most `max_operand_digits` digits. With `honor_conditionals` result is same as
+/// of `ConditionalMemory`, otherwise as of `Memory`.
+pub(crate) fn run_stream(
+    reader: impl Read,
+    honor_conditionals: bool,
+    arithmetic: Arithmetic,
+    max_operand_digits: usize,
+) -> Result<u128, Day03Error> {
+    let mut vm = Vm::new(honor_conditionals, arithmetic);
+
+    for token in StreamLexer::new(reader).with_max_operand_digits(max_operand_digits) {
+        vm.exec(&Instr::from(token?))?;
+    }
 
-        let dont_idxs = s
-            .match_indices(DISABLE_INSTR)
-            .map(|(start, _)| start)
-            .collect::<Vec<_>>();
+    Ok(vm.acc())
+}
 
-        let mut enabled_ranges: Vec<(usize, usize)> = Vec::new();
+/// Byte ranges of `s` in which instructions are enabled. Memory starts enabled, `don't()` closes
+/// current region and first following `do()` opens new one. Repeated `do()` or `don't()` are
+/// no-ops.
+fn enabled_regions(s: &str) -> Result<Vec<(usize, usize)>, Day03Error> {
+    let mut enabled_ranges: Vec<(usize, usize)> = Vec::new();
+    let mut enabled_region_start = Some(0);
+
+    for token in Lexer::new(s) {
+        let token = token?;
+
+        match (token.kind, enabled_region_start) {
+            (TokenKind::Dont, Some(start)) => {
+                info!("enabled region: {} - {}", start, token.offset);
+                enabled_ranges.push((start, token.offset));
+                enabled_region_start = None;
+            }
+            (TokenKind::Do, None) => enabled_region_start = Some(token.offset + token.len),
+            _ => {}
+        }
+    }
 
-        let mut enabled_region_start = 0;
+    if let Some(start) = enabled_region_start {
+        info!("enabled region: {} - {}", start, s.len());
+        enabled_ranges.push((start, s.len()));
+    }
 
-        for disabled_start in dont_idxs.iter() {
-            if *disabled_start < enabled_region_start {
-                continue;
-            }
+    Ok(enabled_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enabled_regions_for_any_interleaving() {
+        let cases: [(&str, &[(usize, usize)]); 9] = [
+            ("", &[(0, 0)]),
+            ("mul(1,2)", &[(0, 8)]),
+            ("do()mul(1,2)", &[(0, 12)]),
+            ("mul(1,2)don't()", &[(0, 8)]),
+            ("don't()mul(1,2)do()", &[(0, 0), (19, 19)]),
+            ("a don't()b do()c", &[(0, 2), (15, 16)]),
+            ("a do()b don't()c", &[(0, 8)]),
+            ("a don't()b don't()c do()d do()e", &[(0, 2), (24, 31)]),
+            ("a don't()b do()c don't()d", &[(0, 2), (15, 17)]),
+        ];
+
+        for (input, expected) in cases {
+            let regions = enabled_regions(input).unwrap();
+            assert_eq!(expected, &regions[..], "regions of '{input}'");
+        }
+    }
+
+    #[test]
+    fn conditional_memory_without_dont() {
+        let memory = "mul(2,3)do()mul(4,5)".parse::<ConditionalMemory>().unwrap();
+
+        assert_eq!(26, memory.run(Arithmetic::Checked).unwrap());
+    }
 
-            enabled_ranges.push((enabled_region_start, *disabled_start));
+    #[test]
+    fn operands_longer_than_limit_are_ignored() {
+        let input = "mul(1234,2)mul(12,3)";
 
-            info!(
-                "enabled region: {} - {}",
-                enabled_region_start, disabled_start
-            );
+        let default = input.parse::<Memory>().unwrap();
+        let relaxed = Memory::parse_with(input, 4).unwrap();
 
-            let Some(next_enable) = do_idxs.iter().find(|do_idx| *do_idx > disabled_start) else {
-                break;
+        assert_eq!(36, default.run(Arithmetic::Checked).unwrap());
+        assert_eq!(2504, relaxed.run(Arithmetic::Checked).unwrap());
+    }
+
+    #[test]
+    fn stream_matches_in_memory_evaluation() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+        let run = |honor_conditionals| {
+            run_stream(
+                input.as_bytes(),
+                honor_conditionals,
+                Arithmetic::Checked,
+                MAX_OPERAND_DIGITS,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(161, run(false));
+        assert_eq!(48, run(true));
+    }
+
+    #[test]
+    fn stream_honors_operand_digit_limit() {
+        let input = "mul(1234,2)don't()mul(12345,3)do()mul(12,3)";
+
+        for max_operand_digits in [3, 4, 5] {
+            let memory = Memory::parse_with(input, max_operand_digits).unwrap();
+            let conditional = ConditionalMemory::parse_with(input, max_operand_digits).unwrap();
+            let run = |honor_conditionals| {
+                run_stream(
+                    input.as_bytes(),
+                    honor_conditionals,
+                    Arithmetic::Checked,
+                    max_operand_digits,
+                )
+                .unwrap()
             };
 
-            enabled_region_start = *next_enable;
+            assert_eq!(memory.run(Arithmetic::Checked).unwrap(), run(false));
+            assert_eq!(conditional.run(Arithmetic::Checked).unwrap(), run(true));
         }
+        assert_eq!(
+            2504,
+            run_stream(input.as_bytes(), true, Arithmetic::Checked, 4).unwrap()
+        );
+    }
 
-        if enabled_region_start > *dont_idxs.last().unwrap() {
-            enabled_ranges.push((enabled_region_start, s.len()));
-        }
+    #[test]
+    fn overflow_handling_per_arithmetic() {
+        let input = "xmul(4294967296,4294967296)don't()mul(1,1)";
+        let memory = Memory::parse_with(input, 20).unwrap();
+
+        let err = memory.run(Arithmetic::Checked).unwrap_err();
+        assert!(matches!(
+            err,
+            Day03Error::MulOverflowError { offset: 1, .. }
+        ));
+
+        assert_eq!(
+            u128::from(u64::MAX),
+            memory.run(Arithmetic::Saturating).unwrap()
+        );
+        assert_eq!(1 << 64 | 1, memory.run(Arithmetic::Wide).unwrap());
+    }
 
-        let memory_regions = enabled_ranges
-            .into_iter()
-            .map(|(start, end)| &s[start..end])
-            .map(|reg| reg.parse::<Memory>())
-            .collect::<Result<Vec<_>, _>>()?;
+    #[test]
+    fn operands_wider_than_u64_are_evaluated_wide() {
+        // 20 digit operand 2^64 does not fit into u64
+        let input = "mul(18446744073709551616,2)mul(3,4)";
+        let memory = Memory::parse_with(input, 20).unwrap();
+
+        assert_eq!((1 << 65) + 12, memory.run(Arithmetic::Wide).unwrap());
+        assert!(matches!(
+            memory.run(Arithmetic::Checked),
+            Err(Day03Error::MulOverflowError { offset: 0, .. })
+        ));
+        assert_eq!(
+            u128::from(u64::MAX),
+            memory.run(Arithmetic::Saturating).unwrap()
+        );
+
+        // operand not fitting even into u128 makes instruction corrupted
+        let input = format!("mul({},2)mul(3,4)", "9".repeat(40));
+        let memory = Memory::parse_with(&input, 40).unwrap();
+        assert_eq!(12, memory.run(Arithmetic::Wide).unwrap());
+    }
 
-        Ok(ConditionalMemory::new(memory_regions))
+    #[test]
+    fn conditional_memory_keeps_absolute_offsets() {
+        let input = "don't()mul(1,1)do()mul(18446744073709551615,2)";
+        let memory = ConditionalMemory::parse_with(input, 20).unwrap();
+
+        let err = memory.run(Arithmetic::Checked).unwrap_err();
+        assert!(matches!(
+            err,
+            Day03Error::MulOverflowError { offset: 19, .. }
+        ));
     }
 }
@@ -0,0 +1,203 @@
+use std::io::{ErrorKind, Read};
+
+use crate::{
+    error::Day03Error,
+    lexer::{InstrMatcher, Token},
+};
+
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Lexes instructions from `Read` source using fixed size buffer, so memory use does not depend
+/// on input size. Only instructions are yielded, corrupted bytes are skipped without producing
+/// `TokenKind::Unknown` tokens. Offsets are relative to start of stream.
+pub(crate) struct StreamLexer<R> {
+    reader: R,
+    matcher: InstrMatcher,
+    buf: Vec<u8>,
+    /// Next position in `buf` to be scanned
+    start: usize,
+    /// Number of valid bytes in `buf`
+    end: usize,
+    /// Stream offset of `buf[0]`
+    consumed: usize,
+    eof: bool,
+}
+
+impl<R: Read> StreamLexer<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self::with_buffer_size(reader, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Buffer is grown to at least twice the matcher window so every refill makes progress
+    pub(crate) fn with_buffer_size(reader: R, buffer_size: usize) -> Self {
+        let matcher = InstrMatcher::default();
+
+        Self {
+            reader,
+            matcher,
+            buf: vec![0; buffer_size.max(2 * matcher.window())],
+            start: 0,
+            end: 0,
+            consumed: 0,
+            eof: false,
+        }
+    }
+
+    /// Accepts `mul` operands with at most `max_operand_digits` digits, buffer is grown if the
+    /// wider matcher window needs it
+    pub(crate) fn with_max_operand_digits(mut self, max_operand_digits: usize) -> Self {
+        self.matcher = InstrMatcher::new(max_operand_digits);
+        let min_len = 2 * self.matcher.window();
+        if self.buf.len() < min_len {
+            self.buf.resize(min_len, 0);
+        }
+        self
+    }
+
+    /// Moves unscanned tail of buffer to front and reads more data after it. Tail is shorter
+    /// than matcher window, so instruction split across reads is matched once it is complete.
+    fn refill(&mut self) -> Result<(), Day03Error> {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.consumed += self.start;
+        self.end -= self.start;
+        self.start = 0;
+
+        loop {
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    return Ok(());
+                }
+                Ok(read) => {
+                    self.end += read;
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(Day03Error::MemoryReadError {
+                        offset: self.consumed + self.end,
+                        error_msg: e.to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamLexer<R> {
+    type Item = Result<Token, Day03Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // positions closer than window to end of buffer could be start of instruction whose
+            // rest was not read yet, those are scanned after refill
+            let scan_limit = if self.eof {
+                self.end
+            } else {
+                self.end.saturating_sub(self.matcher.window() - 1)
+            };
+
+            while self.start < scan_limit {
+                match self.matcher.match_instr(&self.buf[self.start..self.end]) {
+                    Ok(Some((kind, len))) => {
+                        let offset = self.consumed + self.start;
+                        self.start += len;
+                        return Some(Ok(Token { kind, offset, len }));
+                    }
+                    Ok(None) => self.start += 1,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            if self.eof {
+                return None;
+            }
+
+            if let Err(e) = self.refill() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{Lexer, TokenKind};
+
+    const SAMPLE: &str =
+        r"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    /// Reader which returns at most `chunk` bytes per read call
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn instructions_split_across_reads_match_in_memory_lexer() {
+        let expected = Lexer::new(SAMPLE)
+            .filter(|t| {
+                !matches!(
+                    t,
+                    Ok(Token {
+                        kind: TokenKind::Unknown,
+                        ..
+                    })
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        for chunk in 1..SAMPLE.len() {
+            let reader = Chunked {
+                data: SAMPLE.as_bytes(),
+                chunk,
+            };
+            let tokens = StreamLexer::with_buffer_size(reader, 0)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(expected, tokens, "chunk size {chunk}");
+        }
+    }
+
+    #[test]
+    fn wider_operand_limit_grows_buffer() {
+        let input = "mul(18446744073709551616,2)";
+
+        for chunk in 1..input.len() {
+            let reader = Chunked {
+                data: input.as_bytes(),
+                chunk,
+            };
+            let tokens = StreamLexer::with_buffer_size(reader, 0)
+                .with_max_operand_digits(20)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(1, tokens.len(), "chunk size {chunk}");
+            assert_eq!(input.len(), tokens[0].len);
+        }
+    }
+
+    #[test]
+    fn non_utf8_bytes_are_skipped() {
+        let data = b"\xff\xfemul(2,3)\xc3";
+        let tokens = StreamLexer::new(&data[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(1, tokens.len());
+        assert_eq!(2, tokens[0].offset);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    error::Day03Error,
+    lexer::{Token, TokenKind},
+    models::MulInstr,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Opcode {
+    Mul(MulInstr),
+    Enable,
+    Disable,
+    Nop,
+}
+
+impl From<TokenKind> for Opcode {
+    fn from(value: TokenKind) -> Self {
+        match value {
+            TokenKind::Mul(instr) => Opcode::Mul(instr),
+            TokenKind::Do => Opcode::Enable,
+            TokenKind::Dont => Opcode::Disable,
+            TokenKind::Unknown => Opcode::Nop,
+        }
+    }
+}
+
+/// Opcode together with byte offset of instruction it was compiled from
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Instr {
+    pub(crate) offset: usize,
+    pub(crate) op: Opcode,
+}
+
+impl From<Token> for Instr {
+    fn from(value: Token) -> Self {
+        Self {
+            offset: value.offset,
+            op: value.kind.into(),
+        }
+    }
+}
+
+/// How sum of multiplications is accumulated
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum Arithmetic {
+    /// `u64` arithmetic, overflow is reported as error
+    #[default]
+    Checked,
+    /// `u64` arithmetic clamped at `u64::MAX`
+    Saturating,
+    /// `u128` operands and accumulator, overflow is reported as error. There is no big integer
+    /// backend, operands wider than `u128` make instruction corrupted.
+    Wide,
+}
+
+/// Executes opcodes while keeping track of enable flag and sum of executed multiplications
+pub(crate) struct Vm {
+    honor_conditionals: bool,
+    arithmetic: Arithmetic,
+    enabled: bool,
+    acc: u128,
+}
+
+impl Vm {
+    /// When `honor_conditionals` is false `Enable` and `Disable` opcodes are treated as `Nop`
+    pub(crate) fn new(honor_conditionals: bool, arithmetic: Arithmetic) -> Self {
+        Self {
+            honor_conditionals,
+            arithmetic,
+            enabled: true,
+            acc: 0,
+        }
+    }
+
+    pub(crate) fn exec(&mut self, instr: &Instr) -> Result<(), Day03Error> {
+        match &instr.op {
+            Opcode::Mul(mul) if self.enabled => {
+                self.acc = mul.exec(self.acc, self.arithmetic).ok_or_else(|| {
+                    Day03Error::MulOverflowError {
+                        instr: mul.to_string(),
+                        offset: instr.offset,
+                    }
+                })?;
+            }
+            Opcode::Enable if self.honor_conditionals => self.enabled = true,
+            Opcode::Disable if self.honor_conditionals => self.enabled = false,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Sum of multiplications executed so far
+    pub(crate) fn acc(&self) -> u128 {
+        self.acc
+    }
+
+    pub(crate) fn run<'a>(
+        mut self,
+        program: impl IntoIterator<Item = &'a Instr>,
+    ) -> Result<u128, Day03Error> {
+        for instr in program {
+            self.exec(instr)?;
+        }
+
+        Ok(self.acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disable_skips_mul_until_enabled() {
+        let ops = [
+            Opcode::Mul(MulInstr::new(2, 4)),
+            Opcode::Disable,
+            Opcode::Mul(MulInstr::new(5, 5)),
+            Opcode::Nop,
+            Opcode::Enable,
+            Opcode::Mul(MulInstr::new(8, 5)),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(offset, op)| Instr { offset, op })
+        .collect::<Vec<_>>();
+
+        let run = |honor_conditionals| {
+            Vm::new(honor_conditionals, Arithmetic::Checked)
+                .run(&ops)
+                .unwrap()
+        };
+        assert_eq!(48, run(true));
+        assert_eq!(73, run(false));
+    }
+}
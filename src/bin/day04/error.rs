@@ -1,7 +1,18 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Day04Error {
     #[error("could not parse '{input}' into word puzzle: {error_msg}")]
     PuzzleParseError { input: String, error_msg: String },
+
+    #[error("unsupported letter '{letter}' at row {row}, column {col}")]
+    UnsupportedLetterError {
+        letter: char,
+        row: usize,
+        col: usize,
+    },
+
+    #[error("could not search for '{pattern}': {error_msg}")]
+    InvalidPatternError { pattern: String, error_msg: String },
 }
@@ -1,9 +1,12 @@
 mod error;
 mod models;
 
-use advent_of_code_2024::{init, load_day_input};
+use std::time::Instant;
+
+use advent_of_code_2024::{cli_flag_value, has_cli_flag, init, load_day_input};
 use eyre::Context;
-use models::Puzzle;
+use itertools::Itertools;
+use models::{Direction, Puzzle, RenderStyle, Stencil, Symmetry};
 use tracing::info;
 
 fn main() -> eyre::Result<()> {
@@ -20,6 +23,44 @@ fn main() -> eyre::Result<()> {
     let part_2_res = part2(&data)?;
     info!("Part 2 solution: {part_2_res}");
 
+    if has_cli_flag("--render") {
+        let style = if has_cli_flag("--plain") {
+            RenderStyle::Plain
+        } else {
+            RenderStyle::Ansi
+        };
+
+        info!("rendering part 1 matches...");
+        let rendered = render_matches(&data, style)?;
+        println!("{rendered}");
+    }
+
+    if let Some(word) = cli_flag_value("--word") {
+        info!("counting '{word}'...");
+        let word_res = count_word(&data, &word)?;
+        info!("Word count: {word_res}");
+    }
+
+    if has_cli_flag("--bench") {
+        let size = cli_flag_value("--bench-size")
+            .map(|size| size.parse::<usize>())
+            .transpose()
+            .wrap_err("bench size must be a number")?
+            .unwrap_or(2000);
+
+        bench(size)?;
+    }
+
+    if let Some(stencil_path) = cli_flag_value("--stencil") {
+        let symmetry = cli_flag_value("--symmetry").unwrap_or_else(|| "all".to_owned());
+        let stencil = std::fs::read_to_string(&stencil_path)
+            .wrap_err_with(|| format!("failed to read stencil from {stencil_path}"))?;
+
+        info!("counting stencil from {stencil_path} with {symmetry} symmetry...");
+        let stencil_res = count_stencil(&data, &stencil, &symmetry)?;
+        info!("Stencil count: {stencil_res}");
+    }
+
     Ok(())
 }
 
@@ -29,7 +70,8 @@ fn part1(data: &str) -> eyre::Result<usize> {
     let res = data
         .parse::<Puzzle>()
         .wrap_err("Failed to parse word puzzle")?
-        .count_pattern(XMAS);
+        .count_pattern(XMAS)
+        .wrap_err("Failed to search word puzzle")?;
 
     Ok(res)
 }
@@ -40,7 +82,111 @@ fn part2(data: &str) -> eyre::Result<usize> {
     let res = data
         .parse::<Puzzle>()
         .wrap_err("Failed to parse word puzzle")?
-        .count_x_pattern(MAS);
+        .count_x_pattern(MAS)
+        .wrap_err("Failed to search word puzzle")?;
+
+    Ok(res)
+}
+
+/// Counts `word` in all directions, letters of any alphabet are supported
+fn count_word(data: &str, word: &str) -> eyre::Result<usize> {
+    let word = word.chars().collect::<Vec<_>>();
+
+    let res = data
+        .parse::<Puzzle<char>>()
+        .wrap_err("Failed to parse word puzzle")?
+        .count_pattern(&word)
+        .wrap_err("Failed to search word puzzle")?;
+
+    Ok(res)
+}
+
+/// Compares generic search with bitboard path on generated `size`x`size` grid
+fn bench(size: usize) -> eyre::Result<()> {
+    const XMAS: &[u8] = b"XMAS";
+
+    info!("generating {size}x{size} grid...");
+    let puzzle = generate_puzzle(size, 0x2024)
+        .parse::<Puzzle>()
+        .wrap_err("Failed to parse generated word puzzle")?;
+
+    let start = Instant::now();
+    let generic_res = puzzle
+        .find_words(&[XMAS], &Direction::ALL)
+        .wrap_err("Failed to search word puzzle")?
+        .len();
+    let generic_elapsed = start.elapsed();
+    info!(
+        "generic search found {generic_res} in {time}ms",
+        time = generic_elapsed.as_millis()
+    );
+
+    let start = Instant::now();
+    let bitboard_res = puzzle
+        .count_pattern(XMAS)
+        .wrap_err("Failed to search word puzzle")?;
+    let bitboard_elapsed = start.elapsed();
+    info!(
+        "bitboard search found {bitboard_res} in {time}ms",
+        time = bitboard_elapsed.as_millis()
+    );
+
+    eyre::ensure!(
+        generic_res == bitboard_res,
+        "search results differ: {generic_res} != {bitboard_res}"
+    );
+    info!(
+        "speedup: {:.1}x",
+        generic_elapsed.as_secs_f64() / bitboard_elapsed.as_secs_f64()
+    );
+
+    Ok(())
+}
+
+/// Square grid of `XMAS` letters from xorshift generator, same `seed` gives same grid
+fn generate_puzzle(size: usize, seed: u64) -> String {
+    const LETTERS: &[u8] = b"XMAS";
+
+    let mut state = seed.max(1);
+    let mut next_letter = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        char::from(LETTERS[(state % LETTERS.len() as u64) as usize])
+    };
+
+    (0..size)
+        .map(|_| (0..size).map(|_| next_letter()).collect::<String>())
+        .join("\n")
+}
+
+/// Grid with only letters of `XMAS` occurrences visible
+fn render_matches(data: &str, style: RenderStyle) -> eyre::Result<String> {
+    const XMAS: &[u8] = b"XMAS";
+
+    let puzzle = data
+        .parse::<Puzzle>()
+        .wrap_err("Failed to parse word puzzle")?;
+    let matches = puzzle
+        .find_words(&[XMAS], &Direction::ALL)
+        .wrap_err("Failed to search word puzzle")?;
+
+    Ok(puzzle.render(&matches, style))
+}
+
+/// Counts occurrences of stencil given in text form, `.` cells match any letter
+fn count_stencil(data: &str, stencil: &str, symmetry: &str) -> eyre::Result<usize> {
+    let stencil = stencil
+        .parse::<Stencil>()
+        .wrap_err("Failed to parse stencil")?;
+    let symmetry = symmetry
+        .parse::<Symmetry>()
+        .wrap_err("Failed to parse stencil symmetry")?;
+
+    let res = data
+        .parse::<Puzzle>()
+        .wrap_err("Failed to parse word puzzle")?
+        .count_stencil(&stencil, symmetry);
 
     Ok(res)
 }
@@ -67,6 +213,84 @@ MXMXAXMASX";
         assert_eq!(18, part1_res);
     }
 
+    #[test]
+    fn bitboard_and_generic_search_agree_on_generated_grid() {
+        let puzzle = generate_puzzle(97, 7).parse::<Puzzle>().unwrap();
+
+        let generic = puzzle.find_words(&[b"XMAS"], &Direction::ALL).unwrap();
+
+        assert_eq!(generic.len(), puzzle.count_pattern(b"XMAS").unwrap());
+    }
+
+    #[test]
+    fn count_word_in_any_alphabet() {
+        assert_eq!(18, count_word(SAMPLE, "XMAS").unwrap());
+        assert_eq!(2, count_word("ΑΛΦΑ\nΑΦΛΑ", "ΑΛΦΑ").unwrap());
+    }
+
+    #[test]
+    fn render_matches_puzzle_statement() {
+        let expected = r"....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+";
+
+        let rendered = render_matches(SAMPLE, RenderStyle::Plain).unwrap();
+
+        assert_eq!(expected, rendered);
+    }
+
+    #[test]
+    fn x_mas_stencil_matches_part_2() {
+        let res = count_stencil(SAMPLE, "M.S\n.A.\nM.S", "rotations").unwrap();
+
+        assert_eq!(9, res);
+    }
+
+    #[test]
+    fn find_words_reports_location_and_direction() {
+        let puzzle = "..X...\n.SAMX.\n.A..A.\nXMAS.S\n.X...."
+            .parse::<Puzzle>()
+            .unwrap();
+
+        let mut matches = puzzle.find_words(&[b"XMAS"], &Direction::ALL).unwrap();
+        matches.sort_by_key(|m| (m.row, m.col));
+
+        let found = matches
+            .iter()
+            .map(|m| (m.row, m.col, m.direction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, 2, Direction::DownRight),
+                (1, 4, Direction::Left),
+                (3, 0, Direction::Right),
+                (4, 1, Direction::Up),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn find_several_words_in_restricted_directions() {
+        let puzzle = SAMPLE.parse::<Puzzle>().unwrap();
+
+        let matches = puzzle
+            .find_words(&[b"XMAS", b"SAMX"], &[Direction::Right])
+            .unwrap();
+        let counts = matches.iter().counts_by(|m| m.word);
+
+        assert_eq!(Some(&3), counts.get(&0));
+        assert_eq!(Some(&2), counts.get(&1));
+    }
+
     #[test]
     fn part_2_sample_data() {
         let part2_res = part2(SAMPLE).unwrap();
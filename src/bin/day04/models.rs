@@ -1,237 +1,837 @@
-use std::str::FromStr;
+use std::{fmt::Debug, hash::Hash, str::FromStr};
 
+use aho_corasick::AhoCorasick;
 use itertools::Itertools;
-use tracing::{info, info_span, warn};
+use rustc_hash::FxHashMap;
+use tracing::{debug, info};
 
 use crate::error::Day04Error;
 
-pub(crate) struct Puzzle {
-    letters: Vec<Vec<u8>>,
+/// Value of single grid cell. `u8` covers ASCII and Latin-1 puzzles, `char` accepts letters of
+/// any alphabet at cost of four times larger grid.
+pub(crate) trait Letter: Copy + Eq + Hash + Debug {
+    fn from_char(c: char) -> Option<Self>;
+
+    fn to_char(self) -> char;
+
+    /// Appends byte representation of letter, used when searching with Aho-Corasick automaton
+    fn encode(self, buf: &mut Vec<u8>);
 }
 
-impl Puzzle {
-    fn new(letters: Vec<Vec<u8>>) -> Self {
-        Self { letters }
+impl Letter for u8 {
+    fn from_char(c: char) -> Option<Self> {
+        u8::try_from(c).ok()
     }
 
-    pub(crate) fn count_pattern(&self, pattern: &[u8]) -> usize {
-        let rows = self.letters.len();
-        let cols = self.letters[0].len();
+    fn to_char(self) -> char {
+        char::from(self)
+    }
 
-        let mut counter = 0;
-        let mut temp_buf = Vec::<u8>::with_capacity(pattern.len());
-        for row_idx in 0..rows {
-            for col_idx in 0..cols {
-                let span = info_span!("pattern match", row = row_idx, col = col_idx);
-                let _span_guard = span.enter();
+    fn encode(self, buf: &mut Vec<u8>) {
+        buf.push(self);
+    }
+}
 
-                let letter = self.letters[row_idx][col_idx];
+impl Letter for char {
+    fn from_char(c: char) -> Option<Self> {
+        Some(c)
+    }
 
-                if letter == pattern[0] {
-                    // check line forwards
-                    if col_idx + pattern.len() <= cols
-                        && *pattern == self.letters[row_idx][col_idx..(col_idx + pattern.len())]
-                    {
-                        counter += 1;
-                    }
+    fn to_char(self) -> char {
+        self
+    }
 
-                    // check line backwards
-                    if col_idx + 1 >= pattern.len() {
-                        let start = col_idx + 1 - pattern.len();
-                        let end = start + pattern.len();
-                        let slice = &self.letters[row_idx][start..end];
-                        if cmp_rev(pattern, slice) {
-                            counter += 1;
-                        }
-                    }
+    /// UTF-8 encoding, match of encoded word in encoded line always starts at letter boundary
+    fn encode(self, buf: &mut Vec<u8>) {
+        let mut char_buf = [0; 4];
+        buf.extend_from_slice(self.encode_utf8(&mut char_buf).as_bytes());
+    }
+}
 
-                    // check col down
-                    if row_idx + pattern.len() <= rows {
-                        temp_buf.clear();
+fn display_word<L: Letter>(word: &[L]) -> String {
+    word.iter().map(|l| l.to_char()).collect()
+}
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx + i][col_idx];
-                            temp_buf.push(letter);
-                        }
+/// Reading direction in word search grid
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
-                    }
+impl Direction {
+    pub(crate) const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// Row and column change of single step in this direction
+    pub(crate) fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+            Direction::DownRight => (1, 1),
+        }
+    }
 
-                    // check col up
-                    if row_idx + 1 >= pattern.len() {
-                        temp_buf.clear();
+    pub(crate) fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+}
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx - i][col_idx];
-                            temp_buf.push(letter);
-                        }
+/// Single occurrence of searched word
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct WordMatch {
+    /// Index of matched word in searched word list
+    pub(crate) word: usize,
+    pub(crate) row: usize,
+    pub(crate) col: usize,
+    pub(crate) direction: Direction,
+    /// Number of letters in matched word
+    pub(crate) len: usize,
+}
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
-                    }
+/// How matched letters are displayed by `Puzzle::render`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum RenderStyle {
+    /// Matched letters as is, suitable for comparing with expected output
+    Plain,
+    /// Each match gets its own ANSI colour, letters in several matches use colour of last one
+    Ansi,
+}
 
-                    // check diag right down
-                    if row_idx + pattern.len() <= rows && col_idx + pattern.len() <= cols {
-                        temp_buf.clear();
+/// Word search grid stored row by row in single buffer
+pub(crate) struct Puzzle<L = u8> {
+    letters: Vec<L>,
+    rows: usize,
+    cols: usize,
+}
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx + i][col_idx + i];
-                            temp_buf.push(letter);
-                        }
+impl<L: Letter> Puzzle<L> {
+    fn new(letters: Vec<L>, rows: usize, cols: usize) -> Self {
+        Self {
+            letters,
+            rows,
+            cols,
+        }
+    }
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
-                    }
+    fn letter(&self, row: usize, col: usize) -> L {
+        self.letters[row * self.cols + col]
+    }
 
-                    // check diag right up
-                    if row_idx + 1 >= pattern.len() && col_idx + pattern.len() <= cols {
-                        temp_buf.clear();
+    /// Counts occurrences of `pattern` in all eight directions
+    pub(crate) fn count_pattern(&self, pattern: &[L]) -> Result<usize, Day04Error> {
+        if pattern.is_empty() {
+            return Err(Day04Error::InvalidPatternError {
+                pattern: String::new(),
+                error_msg: "pattern is empty".to_owned(),
+            });
+        }
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx - i][col_idx + i];
-                            temp_buf.push(letter);
-                        }
+        let boards = pattern
+            .iter()
+            .unique()
+            .map(|letter| (*letter, self.letter_board(*letter)))
+            .collect::<FxHashMap<_, _>>();
+
+        let mut total = 0;
+        for direction in Direction::ALL {
+            let (row_delta, col_delta) = direction.delta();
+
+            // cell stays candidate while letter `k` steps away in `direction` is `pattern[k]`
+            let mut candidates = boards[&pattern[0]].clone();
+            for (k, letter) in pattern.iter().enumerate().skip(1) {
+                let k = k as isize;
+                candidates.and_shifted(&boards[letter], k * row_delta, k * col_delta);
+            }
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
-                    }
+            let count = candidates.count_ones();
+            debug!("{count} matches reading {direction:?}");
+            total += count;
+        }
+
+        info!(
+            "found {total} matches of '{}' in {}x{} grid",
+            display_word(pattern),
+            self.rows,
+            self.cols
+        );
 
-                    // check diag left down
-                    if row_idx + pattern.len() <= rows && col_idx + 1 >= pattern.len() {
-                        temp_buf.clear();
+        Ok(total)
+    }
+
+    /// Bitboard of cells holding `letter`
+    fn letter_board(&self, letter: L) -> Bitboard {
+        let mut board = Bitboard::empty(self.rows, self.cols);
+
+        for (idx, _) in self
+            .letters
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| **l == letter)
+        {
+            board.set(idx / self.cols, idx % self.cols);
+        }
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx + i][col_idx - i];
-                            temp_buf.push(letter);
-                        }
+        board
+    }
+
+    /// Finds every occurrence of every word when reading grid in given `directions`. Overlapping
+    /// matches are all reported, palindromes are reported once per direction they read in.
+    pub(crate) fn find_words(
+        &self,
+        words: &[&[L]],
+        directions: &[Direction],
+    ) -> Result<Vec<WordMatch>, Day04Error> {
+        if let Some(empty) = words.iter().position(|w| w.is_empty()) {
+            return Err(Day04Error::InvalidPatternError {
+                pattern: String::new(),
+                error_msg: format!("word at index {empty} is empty"),
+            });
+        }
+
+        let encoded_words = words
+            .iter()
+            .map(|word| {
+                let mut buf = Vec::new();
+                word.iter().for_each(|l| l.encode(&mut buf));
+                buf
+            })
+            .collect::<Vec<_>>();
+
+        let searcher =
+            AhoCorasick::new(&encoded_words).map_err(|e| Day04Error::InvalidPatternError {
+                pattern: words.iter().map(|w| display_word(w)).join(", "),
+                error_msg: e.to_string(),
+            })?;
+
+        let mut matches = Vec::new();
+        let mut line_bytes = Vec::<u8>::new();
+        // grid position of every byte in `line_bytes`
+        let mut line_positions = Vec::<(usize, usize)>::new();
+        for &direction in directions {
+            for start in self.line_starts(direction) {
+                line_bytes.clear();
+                line_positions.clear();
+
+                for (row, col) in self.walk(start, direction) {
+                    self.letter(row, col).encode(&mut line_bytes);
+                    line_positions.resize(line_bytes.len(), (row, col));
+                }
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
+                matches.extend(searcher.find_overlapping_iter(&line_bytes).map(|m| {
+                    let (row, col) = line_positions[m.start()];
+                    let word = m.pattern().as_usize();
+                    WordMatch {
+                        word,
+                        row,
+                        col,
+                        direction,
+                        len: words[word].len(),
                     }
+                }));
+            }
+        }
+
+        Ok(matches)
+    }
 
-                    // check diag left up
-                    if row_idx + 1 >= pattern.len() && col_idx + 1 >= pattern.len() {
-                        temp_buf.clear();
+    /// Draws grid keeping only letters which are part of some match, others are replaced with `.`
+    pub(crate) fn render(&self, matches: &[WordMatch], style: RenderStyle) -> String {
+        const HIDDEN: char = '.';
+        const ANSI_RESET: &str = "\x1b[0m";
+        const ANSI_COLOURS: [&str; 6] = [
+            "\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m",
+        ];
 
-                        for i in 0..pattern.len() {
-                            let letter = self.letters[row_idx - i][col_idx - i];
-                            temp_buf.push(letter);
-                        }
+        let (rows, cols) = self.dimensions();
+        let mut highlight: Vec<Vec<Option<usize>>> = vec![vec![None; cols]; rows];
 
-                        if pattern == &temp_buf[..] {
-                            counter += 1;
-                        }
+        for (match_idx, m) in matches.iter().enumerate() {
+            for (row, col) in self.walk((m.row, m.col), m.direction).take(m.len) {
+                highlight[row][col] = Some(match_idx);
+            }
+        }
+
+        let mut rendered = String::with_capacity(rows * (cols + 1));
+        for (row, highlight_row) in highlight.iter().enumerate() {
+            for (col, highlight) in highlight_row.iter().enumerate() {
+                let letter = self.letter(row, col).to_char();
+
+                match (highlight, style) {
+                    (None, _) => rendered.push(HIDDEN),
+                    (Some(_), RenderStyle::Plain) => rendered.push(letter),
+                    (Some(match_idx), RenderStyle::Ansi) => {
+                        rendered.push_str(ANSI_COLOURS[match_idx % ANSI_COLOURS.len()]);
+                        rendered.push(letter);
+                        rendered.push_str(ANSI_RESET);
                     }
                 }
             }
+            rendered.push('\n');
         }
 
-        counter
+        rendered
     }
 
-    pub(crate) fn count_x_pattern(&self, pattern: &[u8]) -> usize {
-        let rows = self.letters.len();
-        let cols = self.letters[0].len();
-        let block_size = pattern.len();
-        let block_size_inc = block_size - 1;
-        let pattern_start = pattern.first().cloned().unwrap();
-        let pattern_end = pattern.last().cloned().unwrap();
-        let quick_check_elems = [pattern_start, pattern_end];
+    fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
 
-        info!("haystack: {rows}x{cols}");
-        info!("needle: {block_size} (+{block_size_inc})");
-        info!(
-            "quick check: {:?}",
-            quick_check_elems
-                .iter()
-                .cloned()
-                .map(char::from)
-                .collect::<Vec<_>>()
-        );
+    /// Position one step from `(row, col)` in `direction`, `None` if outside of grid
+    fn step(&self, (row, col): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (rows, cols) = self.dimensions();
+        let (row_delta, col_delta) = direction.delta();
 
-        let mut counter = 0;
-        let mut left_diag_buf = Vec::<u8>::with_capacity(block_size);
-        let mut right_diag_buf = Vec::<u8>::with_capacity(block_size);
-        for block_row_start in 0..(rows - block_size_inc) {
-            for block_col_start in 0..(cols - block_size_inc) {
-                let block_span = info_span!("", row = block_row_start, col = block_col_start);
-                let _block_span_guard = block_span.enter();
-
-                info!("enter block");
-
-                // quick check corners for fast failure
-                let left_up = self.letters[block_row_start][block_col_start];
-                let right_up = self.letters[block_row_start][block_col_start + block_size_inc];
-                let left_down = self.letters[block_row_start + block_size_inc][block_col_start];
-                let right_down = self.letters[block_row_start + block_size_inc]
-                    [block_col_start + block_size_inc];
-
-                if !quick_check_elems.contains(&left_up)
-                    || !quick_check_elems.contains(&right_up)
-                    || !quick_check_elems.contains(&left_down)
-                    || !quick_check_elems.contains(&right_down)
-                {
-                    warn!("qucik check discard");
-                    continue;
-                }
+        let row = row.checked_add_signed(row_delta).filter(|r| *r < rows)?;
+        let col = col.checked_add_signed(col_delta).filter(|c| *c < cols)?;
 
-                // candidate block, do full check
-                left_diag_buf.clear();
-                right_diag_buf.clear();
-                for i in 0..block_size {
-                    let left_letter = self.letters[block_row_start + i][block_col_start + i];
-                    let right_letter =
-                        self.letters[block_row_start + i][block_col_start + block_size_inc - i];
-                    left_diag_buf.push(left_letter);
-                    right_diag_buf.push(right_letter);
-                }
+        Some((row, col))
+    }
 
-                if is_match(pattern, &left_diag_buf) && is_match(pattern, &right_diag_buf) {
-                    counter += 1;
-                    info!("is match");
-                } else {
-                    warn!("full check discard");
-                }
+    /// Cells from which line reading in `direction` starts, i.e. cells whose predecessor in that
+    /// direction is outside of grid
+    fn line_starts(&self, direction: Direction) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (rows, cols) = self.dimensions();
+
+        (0..rows)
+            .cartesian_product(0..cols)
+            .filter(move |pos| self.step(*pos, direction.opposite()).is_none())
+    }
+
+    /// Positions visited when walking from `start` in `direction` until edge of grid
+    fn walk(
+        &self,
+        start: (usize, usize),
+        direction: Direction,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::successors(Some(start), move |pos| self.step(*pos, direction))
+    }
+
+    /// Counts blocks where odd length `pattern` is written on both diagonals, forwards or
+    /// backwards, crossing in its middle letter
+    pub(crate) fn count_x_pattern(&self, pattern: &[L]) -> Result<usize, Day04Error> {
+        let stencil = Stencil::x_shape(pattern)?;
+
+        Ok(self.count_stencil(&stencil, Symmetry::RotationsAndReflections))
+    }
+
+    /// Counts placements of `stencil` and its distinct `symmetry` variants. Each matching
+    /// (variant, top left corner) pair is counted once.
+    pub(crate) fn count_stencil(&self, stencil: &Stencil<L>, symmetry: Symmetry) -> usize {
+        stencil
+            .variants(symmetry)
+            .iter()
+            .map(|variant| self.find_stencil(variant).count())
+            .sum()
+    }
+
+    /// Top left corners of blocks matching `stencil` exactly as given, without rotating it
+    pub(crate) fn find_stencil<'a>(
+        &'a self,
+        stencil: &'a Stencil<L>,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let (rows, cols) = self.dimensions();
+        let (height, width) = stencil.dimensions();
+
+        let row_starts = 0..(rows + 1).saturating_sub(height);
+        let col_starts = 0..(cols + 1).saturating_sub(width);
+
+        row_starts
+            .cartesian_product(col_starts)
+            .filter(move |(row, col)| {
+                stencil
+                    .letters()
+                    .all(|(r, c, letter)| self.letter(row + r, col + c) == letter)
+            })
+    }
+}
+
+impl<L: Letter> FromStr for Puzzle<L> {
+    type Err = Day04Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let data = parse_grid(s, |c, row, col| {
+            L::from_char(c).ok_or(Day04Error::UnsupportedLetterError {
+                letter: c,
+                row,
+                col,
+            })
+        })?;
+
+        let rows = data.len();
+        let cols = data.first().map_or(0, |row| row.len());
+
+        if let Some((row_idx, row)) = data.iter().enumerate().find(|(_, r)| r.len() != cols) {
+            return Err(Day04Error::PuzzleParseError {
+                input: display_word(row),
+                error_msg: format!("row {row_idx} has {} letters, expected {cols}", row.len()),
+            });
+        }
+
+        Ok(Puzzle::new(data.concat(), rows, cols))
+    }
+}
+
+/// One grid row per line, `cell` is called with every character and its row and column in `s`.
+/// Blank lines around grid and whitespace around each row are skipped, positions still count
+/// them so they point into `s` as given.
+fn parse_grid<T>(
+    s: &str,
+    cell: impl Fn(char, usize, usize) -> Result<T, Day04Error>,
+) -> Result<Vec<Vec<T>>, Day04Error> {
+    let mut rows = s
+        .lines()
+        .enumerate()
+        .skip_while(|(_, l)| l.trim().is_empty())
+        .map(|(row, l)| {
+            let indent = l.chars().take_while(|c| c.is_whitespace()).count();
+
+            l.trim()
+                .chars()
+                .enumerate()
+                .map(|(col, c)| cell(c, row, indent + col))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    while rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+
+    Ok(rows)
+}
+
+/// One bit per grid cell, each row padded to whole number of `u64` words. Bitwise operations
+/// test 64 cells at once and compile to vector instructions where available.
+#[derive(Clone, Debug)]
+struct Bitboard {
+    rows: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+impl Bitboard {
+    fn empty(rows: usize, cols: usize) -> Self {
+        let words_per_row = cols.div_ceil(64);
+
+        Self {
+            rows,
+            words_per_row,
+            bits: vec![0; rows * words_per_row],
+        }
+    }
+
+    fn set(&mut self, row: usize, col: usize) {
+        self.bits[row * self.words_per_row + col / 64] |= 1 << (col % 64);
+    }
+
+    fn word(&self, row: usize, word: isize) -> u64 {
+        usize::try_from(word)
+            .ok()
+            .filter(|w| *w < self.words_per_row)
+            .map_or(0, |w| self.bits[row * self.words_per_row + w])
+    }
+
+    /// Keeps bit `(row, col)` only if `other` has bit `(row + row_delta, col + col_delta)` set.
+    /// Cells shifted in from outside of grid are treated as unset.
+    fn and_shifted(&mut self, other: &Bitboard, row_delta: isize, col_delta: isize) {
+        let word_shift = col_delta.div_euclid(64);
+        let bit_shift = col_delta.rem_euclid(64) as u32;
+
+        for row in 0..self.rows {
+            let row_words = row * self.words_per_row..(row + 1) * self.words_per_row;
+
+            let Some(other_row) = row
+                .checked_add_signed(row_delta)
+                .filter(|r| *r < other.rows)
+            else {
+                self.bits[row_words].fill(0);
+                continue;
+            };
+
+            for (word_idx, word) in self.bits[row_words].iter_mut().enumerate() {
+                let src = word_idx as isize + word_shift;
+                let low = other.word(other_row, src) >> bit_shift;
+                let high = match bit_shift {
+                    0 => 0,
+                    _ => other.word(other_row, src + 1) << (64 - bit_shift),
+                };
+
+                *word &= low | high;
             }
         }
+    }
 
-        counter
+    fn count_ones(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
     }
 }
 
-fn is_match(pattern: &[u8], search: &[u8]) -> bool {
-    pattern == search || cmp_rev(pattern, search)
+/// Which transformations of stencil are also searched for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Symmetry {
+    /// Stencil only as given
+    Identity,
+    /// Stencil rotated by 0, 90, 180 and 270 degrees
+    Rotations,
+    /// Rotations of stencil and of its mirror image
+    RotationsAndReflections,
+}
+
+/// Small rectangular template of letters, `None` cells match any letter
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Stencil<L = u8> {
+    cells: Vec<Vec<Option<L>>>,
 }
 
-fn cmp_rev(pattern: &[u8], rev_search: &[u8]) -> bool {
-    rev_search
-        .iter()
-        .rev()
-        .zip_eq(pattern.iter())
-        .all(|(lhs, rhs)| lhs == rhs)
+impl<L: Letter> Stencil<L> {
+    const WILDCARD: char = '.';
+
+    fn new(cells: Vec<Vec<Option<L>>>) -> Self {
+        Self { cells }
+    }
+
+    /// Odd length `word` written along both diagonals of square, everything else is wildcard
+    pub(crate) fn x_shape(word: &[L]) -> Result<Self, Day04Error> {
+        let size = word.len();
+        let mut cells = vec![vec![None; size]; size];
+
+        for (i, letter) in word.iter().enumerate() {
+            cells[i][i] = Some(*letter);
+            cells[i][size - 1 - i] = Some(*letter);
+        }
+
+        let pattern = word.iter().map(|l| l.to_char()).collect::<String>();
+        Self::new(cells).with_letters(&pattern)
+    }
+
+    /// Stencil without letter cell matches at every position, so it is rejected
+    fn with_letters(self, pattern: &str) -> Result<Self, Day04Error> {
+        if self.letters().next().is_none() {
+            return Err(Day04Error::InvalidPatternError {
+                pattern: pattern.to_owned(),
+                error_msg: "stencil has no letter cell".to_owned(),
+            });
+        }
+
+        Ok(self)
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        let height = self.cells.len();
+        let width = self.cells.first().map_or(0, |row| row.len());
+
+        (height, width)
+    }
+
+    /// Non wildcard cells as `(row, col, letter)`
+    fn letters(&self) -> impl Iterator<Item = (usize, usize, L)> + '_ {
+        self.cells.iter().enumerate().flat_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter_map(move |(col, cell)| cell.map(|letter| (row, col, letter)))
+        })
+    }
+
+    /// Stencil rotated 90 degrees clockwise
+    pub(crate) fn rotated(&self) -> Self {
+        let (height, width) = self.dimensions();
+
+        let cells = (0..width)
+            .map(|col| (0..height).rev().map(|row| self.cells[row][col]).collect())
+            .collect();
+
+        Self::new(cells)
+    }
+
+    /// Stencil mirrored around vertical axis
+    pub(crate) fn reflected(&self) -> Self {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+
+        Self::new(cells)
+    }
+
+    /// Distinct transformations of stencil allowed by `symmetry`, starting with stencil itself
+    pub(crate) fn variants(&self, symmetry: Symmetry) -> Vec<Self> {
+        let rotations = |stencil: &Self| {
+            std::iter::successors(Some(stencil.clone()), |s| Some(s.rotated())).take(4)
+        };
+
+        let candidates: Vec<Self> = match symmetry {
+            Symmetry::Identity => vec![self.clone()],
+            Symmetry::Rotations => rotations(self).collect(),
+            Symmetry::RotationsAndReflections => rotations(self)
+                .chain(rotations(&self.reflected()))
+                .collect(),
+        };
+
+        let mut variants: Vec<Self> = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            if !variants.contains(&candidate) {
+                variants.push(candidate);
+            }
+        }
+
+        variants
+    }
 }
 
-impl FromStr for Puzzle {
+impl FromStr for Symmetry {
     type Err = Day04Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data = s
-            .trim()
-            .lines()
-            .map(|l| l.trim())
-            .map(|l| l.chars().map(u8::try_from).collect::<Result<Vec<_>, _>>())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| Day04Error::PuzzleParseError {
-                input: "".to_owned(),
-                error_msg: e.to_string(),
-            })?;
+        match s.trim() {
+            "identity" => Ok(Symmetry::Identity),
+            "rotations" => Ok(Symmetry::Rotations),
+            "all" => Ok(Symmetry::RotationsAndReflections),
+            other => Err(Day04Error::InvalidPatternError {
+                pattern: other.to_owned(),
+                error_msg: "symmetry must be one of: identity, rotations, all".to_owned(),
+            }),
+        }
+    }
+}
+
+impl<L: Letter> FromStr for Stencil<L> {
+    type Err = Day04Error;
+
+    /// One line per stencil row, `.` is wildcard. Rows must be of equal length.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells = parse_grid(s, |c, row, col| match c {
+            Self::WILDCARD => Ok(None),
+            c => L::from_char(c)
+                .map(Some)
+                .ok_or(Day04Error::UnsupportedLetterError {
+                    letter: c,
+                    row,
+                    col,
+                }),
+        })?;
+
+        if cells.iter().map(|row| row.len()).unique().count() > 1 {
+            return Err(Day04Error::InvalidPatternError {
+                pattern: s.to_owned(),
+                error_msg: "stencil rows are not of equal length".to_owned(),
+            });
+        }
+
+        Self::new(cells).with_letters(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitboard_count_matches_generic_search() {
+        let puzzle = "XMASAMXSX\nMMXMASAMM\nAAMXSAMAA\nSSXMASXSS\nXAMSAMXMA"
+            .parse::<Puzzle>()
+            .unwrap();
+
+        for word in [&b"XMAS"[..], b"XSX", b"A", b"MM"] {
+            let expected = puzzle.find_words(&[word], &Direction::ALL).unwrap().len();
+
+            assert_eq!(expected, puzzle.count_pattern(word).unwrap());
+        }
+    }
+
+    #[test]
+    fn bitboard_shift_crosses_word_boundary() {
+        let mut wide = Bitboard::empty(2, 130);
+        wide.set(0, 63);
+        wide.set(0, 64);
+        wide.set(1, 128);
+        let mut other = Bitboard::empty(2, 130);
+        other.set(0, 64);
+        other.set(1, 65);
+        other.set(1, 129);
+
+        let mut row_shifted = wide.clone();
+        row_shifted.and_shifted(&other, 0, 1);
+        assert_eq!(2, row_shifted.count_ones());
+
+        wide.and_shifted(&other, 1, 1);
+        assert_eq!(1, wide.count_ones());
+    }
+
+    #[test]
+    fn unicode_puzzle_search() {
+        let puzzle = "ЖУКЖ\nКУЖУ\nЖКЖК".parse::<Puzzle<char>>().unwrap();
+        let word = ['Ж', 'У', 'К'];
+
+        let matches = puzzle.find_words(&[&word], &Direction::ALL).unwrap();
+        let found = matches
+            .iter()
+            .map(|m| (m.row, m.col, m.direction))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (0, 3, Direction::Down),
+                (1, 2, Direction::Left),
+                (0, 0, Direction::Right),
+                (2, 0, Direction::UpRight),
+            ],
+            found
+        );
+        assert_eq!(4, puzzle.count_pattern(&word).unwrap());
+        assert_eq!(
+            "ЖУКЖ\nКУЖУ\nЖ..К\n",
+            puzzle.render(&matches, RenderStyle::Plain)
+        );
+    }
+
+    #[test]
+    fn unsupported_letter_reports_position() {
+        let res = "XMAS\nXMЖS".parse::<Puzzle>();
+
+        assert!(matches!(
+            res,
+            Err(Day04Error::UnsupportedLetterError {
+                letter: 'Ж',
+                row: 1,
+                col: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn unsupported_letter_position_counts_indent_and_blank_lines() {
+        let res = "\n  XMAS\n  XMЖS\n".parse::<Puzzle>();
+
+        assert!(matches!(
+            res,
+            Err(Day04Error::UnsupportedLetterError {
+                letter: 'Ж',
+                row: 2,
+                col: 4
+            })
+        ));
+
+        let res = "\n\t.M.\n\tЖA.".parse::<Stencil>();
+
+        assert!(matches!(
+            res,
+            Err(Day04Error::UnsupportedLetterError {
+                letter: 'Ж',
+                row: 2,
+                col: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        let res = "ABC\nAB".parse::<Puzzle>();
+
+        assert!(matches!(res, Err(Day04Error::PuzzleParseError { .. })));
+    }
+
+    #[test]
+    fn render_highlights_matches() {
+        let puzzle = "XMASX\nSAMXA\nAAAAM".parse::<Puzzle>().unwrap();
+        let matches = puzzle.find_words(&[b"XMAS"], &Direction::ALL).unwrap();
+
+        assert_eq!(
+            "XMAS.\nSAMX.\n.....\n",
+            puzzle.render(&matches, RenderStyle::Plain)
+        );
+    }
+
+    #[test]
+    fn render_colours_each_match() {
+        let puzzle = "AB\nCD".parse::<Puzzle>().unwrap();
+        let word_match = |row, col, direction| WordMatch {
+            word: 0,
+            row,
+            col,
+            direction,
+            len: 2,
+        };
+        let matches = [
+            word_match(0, 0, Direction::Right),
+            word_match(1, 1, Direction::Up),
+        ];
+
+        assert_eq!(
+            "\x1b[31mA\x1b[0m\x1b[32mB\x1b[0m\n.\x1b[32mD\x1b[0m\n",
+            puzzle.render(&matches, RenderStyle::Ansi)
+        );
+    }
+
+    #[test]
+    fn x_shape_has_four_distinct_variants() {
+        let stencil = Stencil::x_shape(b"MAS").unwrap();
+
+        let variants = stencil.variants(Symmetry::RotationsAndReflections);
+
+        assert_eq!(4, variants.len());
+        assert_eq!("M.M\n.A.\nS.S".parse::<Stencil>().unwrap(), stencil);
+    }
+
+    #[test]
+    fn stencil_without_letters_is_rejected() {
+        for stencil in [
+            Stencil::x_shape(b""),
+            "".parse::<Stencil>(),
+            "..\n..".parse(),
+        ] {
+            assert!(matches!(
+                stencil,
+                Err(Day04Error::InvalidPatternError { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn count_plus_shaped_mas() {
+        let puzzle = ".M..M.\nMASSAM\n.S..S.".parse::<Puzzle>().unwrap();
+        let plus = ".M.\nMAS\n.S.".parse::<Stencil>().unwrap();
+
+        assert_eq!(1, puzzle.count_stencil(&plus, Symmetry::Identity));
+        assert_eq!(2, puzzle.count_stencil(&plus, Symmetry::Rotations));
+    }
+
+    #[test]
+    fn non_square_glyph_rotations() {
+        let puzzle = "AB\nBA".parse::<Puzzle>().unwrap();
+        let glyph = "AB".parse::<Stencil>().unwrap();
 
-        Ok(Puzzle::new(data))
+        assert_eq!(1, puzzle.count_stencil(&glyph, Symmetry::Identity));
+        assert_eq!(4, puzzle.count_stencil(&glyph, Symmetry::Rotations));
     }
 }
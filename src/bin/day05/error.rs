@@ -1,10 +1,28 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Day05Error {
     #[error("could not parse '{input}' into page order rule: {error_msg}")]
     PageOrderingRuleError { input: String, error_msg: String },
 
     #[error("could not parse '{input}' into page order list: {error_msg}")]
     PageListError { input: String, error_msg: String },
+
+    #[error("page {page} appears twice in '{input}', at index {first_idx} and {second_idx}")]
+    DuplicatePageError {
+        input: String,
+        page: usize,
+        first_idx: usize,
+        second_idx: usize,
+    },
+
+    #[error("page list '{input}' has even length, it has no middle page")]
+    EvenPageListError { input: String },
+
+    #[error("could not parse '{input}' into middle page policy, expected reject, lower or upper")]
+    MiddlePolicyParseError { input: String },
+
+    #[error("page rules form a cycle: {}", cycle.iter().map(ToString::to_string).collect::<Vec<_>>().join(" -> "))]
+    RuleCycleError { cycle: Vec<usize> },
 }
@@ -1,9 +1,9 @@
 mod error;
 mod models;
 
-use advent_of_code_2024::{init, load_day_input};
+use advent_of_code_2024::{cli_flag_value, has_cli_flag, init, load_day_input};
 use eyre::Context;
-use models::{PageOrderList, PageRule, PageRuleList};
+use models::{MiddlePolicy, PageOrderList, PageRule, RuleGraph};
 use tracing::info;
 
 fn main() -> eyre::Result<()> {
@@ -12,46 +12,99 @@ fn main() -> eyre::Result<()> {
     info!("loading data...");
     let data = load_day_input("day05.txt")?;
 
+    let policy = cli_flag_value("--middle")
+        .map(|policy| policy.parse::<MiddlePolicy>())
+        .transpose()
+        .wrap_err("invalid middle page policy")?
+        .unwrap_or_default();
+
     info!("solving part 1...");
-    let part_1_res = part1(&data)?;
+    let part_1_res = part1(&data, policy)?;
     info!("Part 1 solution: {part_1_res}");
 
     info!("solving part 2...");
-    let part_2_res = part2(&data)?;
+    let part_2_res = part2(&data, policy)?;
     info!("Part 2 solution: {part_2_res}");
 
+    if has_cli_flag("--explain") {
+        info!("explaining invalid updates...");
+        explain(&data)?;
+    }
+
     Ok(())
 }
 
-fn part1(data: &str) -> eyre::Result<usize> {
+fn part1(data: &str, policy: MiddlePolicy) -> eyre::Result<usize> {
     info!("Parsing data...");
 
     let (pages, rules) = load_from_data(data)?;
 
     let rv = pages
         .into_iter()
-        .filter(|p| p.is_valid(rules.as_slice()))
-        .map(|valid| valid.middle_page())
-        .sum();
+        .filter(|p| p.is_valid(&rules))
+        .map(|valid| valid.middle_page(policy))
+        .sum::<Result<usize, _>>()
+        .wrap_err("failed to find middle page")?;
     Ok(rv)
 }
 
-fn part2(data: &str) -> eyre::Result<usize> {
+fn part2(data: &str, policy: MiddlePolicy) -> eyre::Result<usize> {
     info!("Parsing data...");
 
     let (pages, rules) = load_from_data(data)?;
 
-    let rv = pages
+    let fixed = pages
         .into_iter()
-        .filter(|p| !p.is_valid(rules.as_slice()))
-        .map(|unordered| unordered.fix(rules.as_slice()))
-        .map(|fixed| fixed.middle_page())
-        .sum();
+        .filter(|p| !p.is_valid(&rules))
+        .map(|unordered| unordered.fix(&rules))
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err("failed to fix page ordering")?;
+
+    let ambiguous = fixed.iter().filter(|f| !f.unique).count();
+    if ambiguous > 0 {
+        info!("{ambiguous} fixed updates have more than one valid order");
+    }
+
+    let rv = fixed
+        .iter()
+        .map(|f| f.pages.middle_page(policy))
+        .sum::<Result<usize, _>>()
+        .wrap_err("failed to find middle page")?;
 
     Ok(rv)
 }
 
-fn load_from_data(data: &str) -> eyre::Result<(Vec<PageOrderList>, PageRuleList)> {
+/// Lists every invalid update together with rules it breaks and its fixed order
+fn explain(data: &str) -> eyre::Result<()> {
+    let (pages, rules) = load_from_data(data)?;
+
+    for update in pages {
+        let violations = update.violations(&rules);
+        if violations.is_empty() {
+            continue;
+        }
+
+        info!("{update}");
+        for violation in violations {
+            info!("  breaks {violation}");
+        }
+
+        let update_str = update.to_string();
+        let fixed = update
+            .fix(&rules)
+            .wrap_err_with(|| format!("failed to fix update {update_str}"))?;
+        let uniqueness = if fixed.unique {
+            "unique"
+        } else {
+            "one of several"
+        };
+        info!("  fixed: {} ({uniqueness})", fixed.pages);
+    }
+
+    Ok(())
+}
+
+fn load_from_data(data: &str) -> eyre::Result<(Vec<PageOrderList>, RuleGraph)> {
     let rules = data
         .lines()
         .take_while(|l| !l.trim().is_empty())
@@ -67,5 +120,60 @@ fn load_from_data(data: &str) -> eyre::Result<(Vec<PageOrderList>, PageRuleList)
         .collect::<Result<Vec<_>, _>>()
         .wrap_err("failed to parse list of page orderings")?;
 
+    let rules = RuleGraph::new(&rules);
+    if let Some(cycle) = rules.find_cycle() {
+        info!("rules are cyclic when all pages are considered: {cycle:?}");
+    }
+
     Ok((pages, rules))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r"47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+    #[test]
+    fn part_1_sample_data() {
+        let res =
+            part1(SAMPLE, MiddlePolicy::default()).expect("part 1 not to error on sample data");
+
+        assert_eq!(143, res);
+    }
+
+    #[test]
+    fn part_2_sample_data() {
+        let res =
+            part2(SAMPLE, MiddlePolicy::default()).expect("part 2 not to error on sample data");
+
+        assert_eq!(123, res);
+    }
+}
@@ -1,10 +1,14 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
+use derive_more::derive::Display;
+use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
-use tracing::warn;
+use tracing::{debug, warn};
 
 use crate::error::Day05Error;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display("{before}|{after}")]
 pub(crate) struct PageRule {
     before: usize,
     after: usize,
@@ -44,7 +48,106 @@ impl FromStr for PageRule {
     }
 }
 
-pub(crate) type PageRuleList = Vec<PageRule>;
+/// Page ordering rules indexed by page, edge `before -> after` for every rule
+#[derive(Default)]
+pub(crate) struct RuleGraph {
+    successors: FxHashMap<usize, FxHashSet<usize>>,
+}
+
+impl RuleGraph {
+    pub(crate) fn new(rules: &[PageRule]) -> Self {
+        let mut successors: FxHashMap<usize, FxHashSet<usize>> = FxHashMap::default();
+        for rule in rules {
+            successors
+                .entry(rule.before)
+                .or_default()
+                .insert(rule.after);
+        }
+
+        Self { successors }
+    }
+
+    /// Pages which rules require to come after `page`
+    fn successors_of(&self, page: usize) -> impl Iterator<Item = usize> + '_ {
+        self.successors.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Checks if there is rule `before|after`
+    fn has_rule(&self, before: usize, after: usize) -> bool {
+        self.successors
+            .get(&before)
+            .is_some_and(|afters| afters.contains(&after))
+    }
+
+    /// Finds cycle among rules which only mention `pages`. Returned path starts and ends with
+    /// same page, e.g. `[1, 2, 3, 1]` for rules `1|2`, `2|3` and `3|1`.
+    pub(crate) fn find_cycle_among(&self, pages: &FxHashSet<usize>) -> Option<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Visit {
+            InProgress,
+            Done,
+        }
+
+        let mut visited: FxHashMap<usize, Visit> = FxHashMap::default();
+        let mut path: Vec<usize> = Vec::new();
+
+        // iterative DFS, stack holds page and its not yet explored successors
+        for &root in pages.iter().sorted() {
+            if visited.contains_key(&root) {
+                continue;
+            }
+
+            let mut stack = vec![(root, self.successors_of(root).collect::<Vec<_>>())];
+            visited.insert(root, Visit::InProgress);
+            path.push(root);
+
+            while let Some((_, pending)) = stack.last_mut() {
+                let Some(next) = pending.pop() else {
+                    let (page, _) = stack.pop().expect("stack is not empty");
+                    visited.insert(page, Visit::Done);
+                    path.pop();
+                    continue;
+                };
+
+                if !pages.contains(&next) {
+                    continue;
+                }
+
+                match visited.get(&next) {
+                    Some(Visit::InProgress) => {
+                        let cycle_start = path
+                            .iter()
+                            .position(|p| *p == next)
+                            .expect("in progress page is on path");
+                        let mut cycle = path[cycle_start..].to_vec();
+                        cycle.push(next);
+                        return Some(cycle);
+                    }
+                    Some(Visit::Done) => {}
+                    None => {
+                        visited.insert(next, Visit::InProgress);
+                        path.push(next);
+                        stack.push((next, self.successors_of(next).collect()));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Cycle among all rules, see `find_cycle_among`
+    pub(crate) fn find_cycle(&self) -> Option<Vec<usize>> {
+        let pages = self
+            .successors
+            .iter()
+            .flat_map(|(before, afters)| std::iter::once(before).chain(afters))
+            .copied()
+            .collect();
+
+        self.find_cycle_among(&pages)
+    }
+}
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 struct PageIndex(usize);
@@ -74,84 +177,212 @@ impl PageOrderList {
         self.page_index.get(&page)
     }
 
-    pub(crate) fn is_valid(&self, rules: &[PageRule]) -> bool {
-        for rule in rules {
-            let before_idx = self.index_of(rule.before);
-            let after_idx = self.index_of(rule.after);
-
-            let (Some(before_idx), Some(after_idx)) = (before_idx, after_idx) else {
-                continue;
-            };
+    /// Checks only rules whose `before` page is part of this list
+    pub(crate) fn is_valid(&self, rules: &RuleGraph) -> bool {
+        self.violations_iter(rules).next().is_none()
+    }
 
-            if before_idx >= after_idx {
-                return false;
-            }
-        }
+    /// Every rule broken by this list, in order of `before` page position
+    pub(crate) fn violations(&self, rules: &RuleGraph) -> Vec<Violation> {
+        self.violations_iter(rules).collect()
+    }
 
-        true
+    fn violations_iter<'a>(&'a self, rules: &'a RuleGraph) -> impl Iterator<Item = Violation> + 'a {
+        self.pages
+            .iter()
+            .enumerate()
+            .flat_map(move |(before_idx, page)| {
+                rules.successors_of(*page).filter_map(move |after| {
+                    let after_idx = self.index_of(after)?;
+
+                    (after_idx.0 < before_idx).then_some(Violation {
+                        rule: PageRule::new(*page, after),
+                        before_idx,
+                        after_idx: after_idx.0,
+                    })
+                })
+            })
     }
 
-    pub(crate) fn middle_page(&self) -> usize {
-        let middle = (self.pages.len() - 1) / 2;
-        self.pages[middle]
+    /// Middle page of list, `policy` decides which page is middle of even length list
+    pub(crate) fn middle_page(&self, policy: MiddlePolicy) -> Result<usize, Day05Error> {
+        let len = self.pages.len();
+
+        let middle = match policy {
+            _ if len % 2 == 1 => len / 2,
+            MiddlePolicy::Lower => {
+                debug!("page list {self} has even length, using lower middle page");
+                len / 2 - 1
+            }
+            MiddlePolicy::Upper => len / 2,
+            MiddlePolicy::Reject => {
+                return Err(Day05Error::EvenPageListError {
+                    input: self.to_string(),
+                })
+            }
+        };
+
+        Ok(self.pages[middle])
     }
 
-    pub(crate) fn fix(mut self, rules: &[PageRule]) -> Self {
-        if self.is_valid(rules) {
+    /// Reorders pages to satisfy `rules`. Fails if rules between pages of this list are cyclic.
+    pub(crate) fn fix(self, rules: &RuleGraph) -> Result<FixedOrder, Day05Error> {
+        let fixed = if self.is_valid(rules) {
             warn!("was valid page, nothing to fix");
-            return self;
-        }
+            self
+        } else {
+            let predecessors = self.predecessor_counts(rules);
 
-        // after -> [before]
-        let mut deps: FxHashMap<usize, FxHashSet<usize>> = FxHashMap::default();
-        let original_set: FxHashSet<usize> = FxHashSet::from_iter(self.pages.iter().cloned());
-        for rule in rules {
-            if !original_set.contains(&rule.before) || !original_set.contains(&rule.after) {
-                continue;
+            match self.sort_by_rules(&predecessors, rules) {
+                Some(sorted) => sorted,
+                None => {
+                    debug!("rules not total on {:?}, sorting topologically", self.pages);
+                    self.topological_sort(predecessors, rules)?
+                }
             }
+        };
 
-            deps.entry(rule.after)
-                .and_modify(|deps| {
-                    deps.insert(rule.before);
-                })
-                .or_insert_with(|| {
-                    let mut set: FxHashSet<usize> = FxHashSet::default();
-                    set.insert(rule.before);
-                    set
-                });
-        }
+        let unique = fixed.is_unique_order(rules);
+        Ok(FixedOrder {
+            pages: fixed,
+            unique,
+        })
+    }
 
-        let mut reordered: Vec<usize> = Vec::with_capacity(self.pages.len());
-        while !self.pages.is_empty() {
-            let starting_size = self.pages.len();
-            self.pages.retain(|page| match deps.get(page) {
-                None => {
-                    reordered.push(*page);
-                    remove_val(&mut deps, page);
+    /// Number of pages in this list that rules require to come before each page
+    fn predecessor_counts(&self, rules: &RuleGraph) -> FxHashMap<usize, usize> {
+        let mut predecessors: FxHashMap<usize, usize> =
+            self.pages.iter().map(|page| (*page, 0)).collect();
 
-                    false
+        for page in self.pages.iter() {
+            for after in rules.successors_of(*page) {
+                if let Some(count) = predecessors.get_mut(&after) {
+                    *count += 1;
                 }
-                _ => true,
-            });
+            }
+        }
+
+        predecessors
+    }
+
+    /// When rules are total on pages of this list, page with `k` predecessors belongs at index
+    /// `k`, so sorting by predecessor count orders pages in `O(n log n)`. `None` if resulting
+    /// order breaks some rule, i.e. rules were not total.
+    fn sort_by_rules(
+        &self,
+        predecessors: &FxHashMap<usize, usize>,
+        rules: &RuleGraph,
+    ) -> Option<Self> {
+        let mut sorted = self.pages.clone();
+        sorted.sort_by_key(|page| predecessors[page]);
+
+        let sorted = Self::new(sorted);
+        sorted.is_valid(rules).then_some(sorted)
+    }
 
-            let remaining_size = self.pages.len();
+    /// Kahn's algorithm, works for any acyclic rules
+    fn topological_sort(
+        &self,
+        mut predecessors: FxHashMap<usize, usize>,
+        rules: &RuleGraph,
+    ) -> Result<Self, Day05Error> {
+        let mut ready: Vec<usize> = self
+            .pages
+            .iter()
+            .rev()
+            .filter(|page| predecessors[*page] == 0)
+            .copied()
+            .collect();
+        let mut reordered: Vec<usize> = Vec::with_capacity(self.pages.len());
+        while let Some(page) = ready.pop() {
+            reordered.push(page);
+
+            for after in rules.successors_of(page) {
+                if let Some(count) = predecessors.get_mut(&after) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(after);
+                    }
+                }
+            }
+        }
 
-            assert!(
-                remaining_size < starting_size,
-                "nothing removed in iteration, we will get stuck in infinite loop"
-            );
+        if reordered.len() < self.pages.len() {
+            let remaining = self
+                .pages
+                .iter()
+                .filter(|page| !reordered.contains(page))
+                .copied()
+                .collect();
+            let cycle = rules
+                .find_cycle_among(&remaining)
+                .expect("unordered pages are part of a cycle");
+
+            return Err(Day05Error::RuleCycleError { cycle });
         }
 
-        Self::new(reordered)
+        Ok(Self::new(reordered))
+    }
+
+    /// Valid order is only one possible if every pair of neighbouring pages is fixed by a rule,
+    /// otherwise swapping such pair gives another valid order
+    fn is_unique_order(&self, rules: &RuleGraph) -> bool {
+        self.pages
+            .iter()
+            .tuple_windows()
+            .all(|(before, after)| rules.has_rule(*before, *after))
     }
 }
 
-fn remove_val(map: &mut FxHashMap<usize, FxHashSet<usize>>, val: &usize) {
-    for vals in map.values_mut() {
-        vals.remove(val);
+impl Display for PageOrderList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pages.iter().join(","))
     }
+}
+
+/// Rule broken by page list, `after` page of rule is found before its `before` page
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display("{rule} (page {} at index {before_idx}, page {} at index {after_idx})", rule.before, rule.after)]
+pub(crate) struct Violation {
+    pub(crate) rule: PageRule,
+    /// Index of rules `before` page in list
+    pub(crate) before_idx: usize,
+    /// Index of rules `after` page in list, always less than `before_idx`
+    pub(crate) after_idx: usize,
+}
 
-    map.retain(|_, vals| !vals.is_empty())
+/// Which page is considered middle of even length page list
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum MiddlePolicy {
+    /// Even length lists have no middle page and are reported as error
+    Reject,
+    /// Page just before middle of list, e.g. `2` for `1,2,3,4`
+    #[default]
+    Lower,
+    /// Page just after middle of list, e.g. `3` for `1,2,3,4`
+    Upper,
+}
+
+impl FromStr for MiddlePolicy {
+    type Err = Day05Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "reject" => Ok(MiddlePolicy::Reject),
+            "lower" => Ok(MiddlePolicy::Lower),
+            "upper" => Ok(MiddlePolicy::Upper),
+            other => Err(Day05Error::MiddlePolicyParseError {
+                input: other.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Result of `PageOrderList::fix`
+pub(crate) struct FixedOrder {
+    pub(crate) pages: PageOrderList,
+    /// False if rules allow several valid orders and `pages` is just one of them
+    pub(crate) unique: bool,
 }
 
 impl FromStr for PageOrderList {
@@ -167,6 +398,164 @@ impl FromStr for PageOrderList {
                 error_msg: e.to_string(),
             })?;
 
+        // page can be printed only once, so duplicate means corrupted update
+        let mut first_seen: FxHashMap<usize, usize> = FxHashMap::default();
+        for (idx, page) in pages.iter().enumerate() {
+            if let Some(first_idx) = first_seen.insert(*page, idx) {
+                return Err(Day05Error::DuplicatePageError {
+                    input: s.to_owned(),
+                    page: *page,
+                    first_idx,
+                    second_idx: idx,
+                });
+            }
+        }
+
         Ok(PageOrderList::new(pages))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(rules: &[(usize, usize)]) -> RuleGraph {
+        let rules = rules
+            .iter()
+            .map(|(before, after)| PageRule::new(*before, *after))
+            .collect::<Vec<_>>();
+
+        RuleGraph::new(&rules)
+    }
+
+    #[test]
+    fn cycle_path_is_reported() {
+        let rules = graph(&[(1, 2), (2, 3), (3, 1), (3, 4)]);
+
+        let cycle = rules.find_cycle().unwrap();
+
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(4, cycle.len());
+        for (before, after) in cycle.iter().tuple_windows() {
+            assert!(rules.successors_of(*before).contains(after));
+        }
+    }
+
+    #[test]
+    fn contradictory_rules_are_cycle() {
+        let rules = graph(&[(1, 2), (2, 1)]);
+
+        assert!(rules.find_cycle().is_some());
+        assert!(rules
+            .find_cycle_among(&FxHashSet::from_iter([1, 3]))
+            .is_none());
+    }
+
+    #[test]
+    fn fix_reports_cycle_instead_of_panicking() {
+        let rules = graph(&[(1, 2), (2, 3), (3, 1)]);
+        let pages = "3,2,1".parse::<PageOrderList>().unwrap();
+
+        let res = pages.fix(&rules);
+
+        assert!(matches!(res, Err(Day05Error::RuleCycleError { .. })));
+    }
+
+    #[test]
+    fn cycle_outside_of_update_is_ignored() {
+        let rules = graph(&[(1, 2), (2, 3), (3, 1), (4, 5)]);
+        let pages = "5,4,1".parse::<PageOrderList>().unwrap();
+
+        let fixed = pages.fix(&rules).unwrap();
+
+        assert_eq!(vec![4, 1, 5], fixed.pages.pages);
+        assert!(!fixed.unique);
+    }
+
+    #[test]
+    fn violations_report_every_broken_rule() {
+        let rules = graph(&[(1, 2), (1, 3), (2, 3), (4, 5)]);
+        let pages = "3,2,1,4".parse::<PageOrderList>().unwrap();
+
+        let violations = pages.violations(&rules);
+
+        let violation = |before, after, before_idx, after_idx| Violation {
+            rule: PageRule::new(before, after),
+            before_idx,
+            after_idx,
+        };
+        let violations = violations
+            .into_iter()
+            .sorted_by_key(|v| (v.before_idx, v.after_idx))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                violation(2, 3, 1, 0),
+                violation(1, 3, 2, 0),
+                violation(1, 2, 2, 1)
+            ],
+            violations
+        );
+    }
+
+    #[test]
+    fn duplicate_page_is_rejected() {
+        let res = "1,2,3,2".parse::<PageOrderList>();
+
+        assert!(matches!(
+            res,
+            Err(Day05Error::DuplicatePageError {
+                page: 2,
+                first_idx: 1,
+                second_idx: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn middle_page_policies() {
+        let odd = "1,2,3".parse::<PageOrderList>().unwrap();
+        let even = "1,2,3,4".parse::<PageOrderList>().unwrap();
+
+        for policy in [
+            MiddlePolicy::Reject,
+            MiddlePolicy::Lower,
+            MiddlePolicy::Upper,
+        ] {
+            assert_eq!(2, odd.middle_page(policy).unwrap());
+        }
+        assert_eq!(2, even.middle_page(MiddlePolicy::default()).unwrap());
+        assert_eq!(2, even.middle_page(MiddlePolicy::Lower).unwrap());
+        assert_eq!(3, even.middle_page(MiddlePolicy::Upper).unwrap());
+        assert!(matches!(
+            even.middle_page(MiddlePolicy::Reject),
+            Err(Day05Error::EvenPageListError { .. })
+        ));
+    }
+
+    #[test]
+    fn total_rules_sort_to_unique_order() {
+        let rules = graph(&[(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)]);
+        let pages = "4,2,3,1".parse::<PageOrderList>().unwrap();
+
+        let fixed = pages.fix(&rules).unwrap();
+
+        assert_eq!(vec![1, 2, 3, 4], fixed.pages.pages);
+        assert!(fixed.unique);
+    }
+
+    #[test]
+    fn partial_rules_fall_back_to_topological_sort() {
+        // no rule puts 2 or 3 before 1, so 1 has single direct predecessor just like 3. Sorting by
+        // predecessor count keeps 1 before 3 and breaks 4|1, topological sort fixes it.
+        let rules = graph(&[(2, 3), (3, 4), (2, 4), (4, 1)]);
+        let pages = "1,4,3,2".parse::<PageOrderList>().unwrap();
+
+        let fixed = pages.fix(&rules).unwrap();
+
+        assert!(fixed.pages.is_valid(&rules));
+        assert_eq!(vec![2, 3, 4, 1], fixed.pages.pages);
+        assert!(fixed.unique);
+    }
+}
@@ -20,9 +20,18 @@ pub(crate) enum Day06Error {
         next_position: (i32, i32),
     },
 
-    #[error("failed to simulte guard movements: {why}")]
-    MovementSimulationError { why: String },
+    #[error("guard at ({},{}) turned {turns} times without making step, it can not leave the cell", position.0, position.1)]
+    GuardEnclosedError {
+        position: (usize, usize),
+        turns: usize,
+    },
+
+    #[error("guard made {max_steps} steps without leaving the map or looping")]
+    StepBudgetExceededError { max_steps: usize },
+
+    #[error("guard left the map although obstacle at ({},{}) should make it loop", obstacle.0, obstacle.1)]
+    LoopExpectedError { obstacle: (usize, usize) },
 
-    #[error("guard stuck in loop")]
-    SimulationLoopError,
+    #[error("failed to write frame {frame}: {error_msg}")]
+    FrameWriteError { frame: usize, error_msg: String },
 }
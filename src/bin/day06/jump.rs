@@ -0,0 +1,493 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rustc_hash::FxHashSet;
+use tracing::debug;
+
+use crate::{
+    error::Day06Error,
+    models::{Guard, Map, MapPosition, Orientation, Reaction, Simulation, SimulationEvent, Turn},
+};
+
+/// Cell in front of tile which makes guard turn, together with direction of the turn
+#[derive(Clone, Copy, Debug)]
+struct Stop {
+    cell: u32,
+    turn: Turn,
+}
+
+/// For every cell and orientation holds cell where guard stops in front of next blocking tile, so
+/// whole straight run of guard is single lookup instead of cell by cell walk
+pub(crate) struct JumpTable {
+    width: usize,
+    height: usize,
+    /// `Orientation::ALL.len()` entries per cell, `None` if guard leaves map
+    stops: Vec<Option<Stop>>,
+}
+
+impl JumpTable {
+    pub(crate) fn new(map: &Map) -> Self {
+        let (width, height) = map.dimension();
+        let mut stops = vec![None; width * height * Orientation::ALL.len()];
+
+        let turn_at = |x: usize, y: usize, facing: Orientation| {
+            let tile = map.at(x as i32, y as i32).expect("valid map position");
+            match tile.reaction(facing) {
+                Reaction::Step => None,
+                Reaction::Turn(turn) => Some(turn),
+            }
+        };
+        let mut set = |x: usize, y: usize, facing: Orientation, stop: Option<Stop>| {
+            stops[(y * width + x) * Orientation::ALL.len() + facing as usize] = stop;
+        };
+
+        // sweep every row and column against guard direction, remembering cell in front of
+        // most recently seen blocking tile
+        for y in 0..height {
+            let mut stop = None;
+            for x in 0..width {
+                set(x, y, Orientation::Left, stop);
+                if let Some(turn) = turn_at(x, y, Orientation::Left) {
+                    let cell = (y * width + x + 1) as u32;
+                    stop = Some(Stop { cell, turn });
+                }
+            }
+
+            let mut stop = None;
+            for x in (0..width).rev() {
+                set(x, y, Orientation::Right, stop);
+                if let Some(turn) = turn_at(x, y, Orientation::Right).filter(|_| x > 0) {
+                    let cell = (y * width + x - 1) as u32;
+                    stop = Some(Stop { cell, turn });
+                }
+            }
+        }
+
+        for x in 0..width {
+            let mut stop = None;
+            for y in 0..height {
+                set(x, y, Orientation::Up, stop);
+                if let Some(turn) = turn_at(x, y, Orientation::Up) {
+                    let cell = ((y + 1) * width + x) as u32;
+                    stop = Some(Stop { cell, turn });
+                }
+            }
+
+            let mut stop = None;
+            for y in (0..height).rev() {
+                set(x, y, Orientation::Down, stop);
+                if let Some(turn) = turn_at(x, y, Orientation::Down).filter(|_| y > 0) {
+                    let cell = ((y - 1) * width + x) as u32;
+                    stop = Some(Stop { cell, turn });
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            stops,
+        }
+    }
+
+    /// Cell where guard stops when walking from `position` in `facing` direction and turn it
+    /// makes there, `None` if guard leaves map. `extra_obstacle` is obstacle not present in map
+    /// the table was built from.
+    fn next_stop(
+        &self,
+        position: (usize, usize),
+        facing: Orientation,
+        extra_obstacle: (usize, usize),
+    ) -> Option<((usize, usize), Turn)> {
+        let (x, y) = position;
+        let stop = self.stops[self.state_idx(position, facing)].map(|stop| {
+            let cell = stop.cell as usize;
+            ((cell % self.width, cell / self.width), stop.turn)
+        });
+
+        let (ox, oy) = extra_obstacle;
+        let in_front = match facing {
+            Orientation::Up => ox == x && oy < y && stop.is_none_or(|((_, sy), _)| oy >= sy),
+            Orientation::Down => ox == x && oy > y && stop.is_none_or(|((_, sy), _)| oy <= sy),
+            Orientation::Left => oy == y && ox < x && stop.is_none_or(|((sx, _), _)| ox >= sx),
+            Orientation::Right => oy == y && ox > x && stop.is_none_or(|((sx, _), _)| ox <= sx),
+        };
+
+        if !in_front {
+            return stop;
+        }
+
+        let stop = match facing {
+            Orientation::Up => (ox, oy + 1),
+            Orientation::Down => (ox, oy - 1),
+            Orientation::Left => (ox + 1, oy),
+            Orientation::Right => (ox - 1, oy),
+        };
+        Some((stop, Turn::Right))
+    }
+
+    /// Guard at `position` stops right away whichever way it faces, without any extra obstacle
+    fn is_enclosed(&self, position: (usize, usize)) -> bool {
+        let cell = (position.1 * self.width + position.0) as u32;
+        Orientation::ALL.into_iter().all(|facing| {
+            self.stops[self.state_idx(position, facing)].is_some_and(|stop| stop.cell == cell)
+        })
+    }
+
+    fn state_idx(&self, position: (usize, usize), facing: Orientation) -> usize {
+        (position.1 * self.width + position.0) * Orientation::ALL.len() + facing as usize
+    }
+}
+
+/// Dense set of guard states, only bits which were set are cleared on reset so it can be reused
+/// across simulations without touching whole map
+pub(crate) struct VisitedStates {
+    bits: Vec<u64>,
+    touched: Vec<usize>,
+}
+
+impl VisitedStates {
+    pub(crate) fn new(jumps: &JumpTable) -> Self {
+        let states = jumps.width * jumps.height * Orientation::ALL.len();
+
+        Self {
+            bits: vec![0; states.div_ceil(64)],
+            touched: Vec::with_capacity(1 << 8),
+        }
+    }
+
+    /// Returns false if state was already present
+    fn insert(&mut self, state_idx: usize) -> bool {
+        let (word, bit) = (state_idx / 64, 1 << (state_idx % 64));
+        if self.bits[word] & bit != 0 {
+            return false;
+        }
+
+        self.bits[word] |= bit;
+        self.touched.push(word);
+        true
+    }
+
+    fn clear(&mut self) {
+        for word in self.touched.drain(..) {
+            self.bits[word] = 0;
+        }
+    }
+}
+
+/// Checks if guard starting as `guard` loops once `obstacle` is added to map of `jumps`. Returns
+/// first repeated guard state, guard in front of blocking tile before turning, which lies on
+/// the loop. Guard sealed in by `obstacle` never leaves map so it loops, guard which can not step
+/// in any direction even without it is error, same as in `Simulation`.
+pub(crate) fn find_loop(
+    jumps: &JumpTable,
+    visited: &mut VisitedStates,
+    guard: Guard,
+    obstacle: (usize, usize),
+) -> Result<Option<Guard>, Day06Error> {
+    visited.clear();
+
+    let mut position = guard.position();
+    let mut facing = guard.facing();
+
+    // only states in front of obstacles are recorded, guard loops iff it reaches one twice
+    while let Some((stop, turn)) = jumps.next_stop(position, facing, obstacle) {
+        if !visited.insert(jumps.state_idx(stop, facing)) {
+            if jumps.is_enclosed(stop) {
+                return Err(Day06Error::GuardEnclosedError {
+                    position: stop,
+                    turns: Orientation::ALL.len(),
+                });
+            }
+
+            return Ok(Some(Guard::new(stop, facing)));
+        }
+
+        position = stop;
+        facing = facing.turned(turn);
+    }
+
+    Ok(None)
+}
+
+/// Obstacle position which makes guard patrol forever
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LoopObstacle {
+    pub(crate) position: (usize, usize),
+    /// First repeated guard state, `cycle` starts there. It may be reached by turn in place, so
+    /// it is one of states `Simulation` records too.
+    pub(crate) entry: Guard,
+}
+
+impl LoopObstacle {
+    /// Guard states of one round of the loop in order starting at `entry`, turns in place
+    /// included. Traced cell by cell, so it is computed only when asked for.
+    pub(crate) fn cycle(&self, map: &Map) -> Result<Vec<Guard>, Day06Error> {
+        let mut cycle = vec![self.entry];
+
+        for event in Simulation::new(self.entry, map).with_extra_obstacle(self.position) {
+            match event? {
+                SimulationEvent::Moved(guard) | SimulationEvent::Turned(guard) => cycle.push(guard),
+                SimulationEvent::LoopDetected(guard) => {
+                    debug_assert_eq!(self.entry, guard, "loop closes at its entry");
+                }
+                SimulationEvent::Exited(_) => {
+                    return Err(Day06Error::LoopExpectedError {
+                        obstacle: self.position,
+                    })
+                }
+            }
+        }
+
+        Ok(cycle)
+    }
+}
+
+/// Number of steps guard makes in one round of loop `cycle`
+pub(crate) fn loop_len(cycle: &[Guard]) -> usize {
+    cycle
+        .iter()
+        .zip(cycle.iter().cycle().skip(1))
+        .filter(|(from, to)| from.position() != to.position())
+        .count()
+}
+
+/// Every obstacle position causing loop of `guard` in order it is reached on original route
+pub(crate) fn find_loop_obstacles(
+    guard: Guard,
+    map: &Map,
+    max_steps: Option<usize>,
+) -> Result<Vec<LoopObstacle>, Day06Error> {
+    let candidates = obstacle_candidates(guard, map, max_steps)?;
+    let jumps = JumpTable::new(map);
+
+    debug!(
+        "searching loops for {} obstacle candidates",
+        candidates.len()
+    );
+    candidates
+        .into_par_iter()
+        .map_init(
+            || VisitedStates::new(&jumps),
+            |visited, candidate| {
+                let entry = find_loop(&jumps, visited, candidate.guard, candidate.position)?;
+
+                Ok(entry.map(|entry| LoopObstacle {
+                    position: candidate.position,
+                    entry,
+                }))
+            },
+        )
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+/// Cell where new obstacle may be placed together with guard state right before it first reaches
+/// that cell, simulation with the obstacle can start from there
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ObstacleCandidate {
+    pub(crate) position: (usize, usize),
+    pub(crate) guard: Guard,
+}
+
+/// Empty cells on guard path in order of first visit. Obstacle placed outside of original path is
+/// never met so it can not cause loop.
+pub(crate) fn obstacle_candidates(
+    guard: Guard,
+    map: &Map,
+    max_steps: Option<usize>,
+) -> Result<Vec<ObstacleCandidate>, Day06Error> {
+    let mut seen: FxHashSet<(usize, usize)> = FxHashSet::default();
+    seen.insert(guard.position());
+
+    let mut candidates = Vec::with_capacity(1 << 12);
+    let mut before = guard;
+
+    for event in Simulation::new(guard, map).with_max_steps(max_steps) {
+        let event = event?;
+
+        if let SimulationEvent::Moved(after) = event {
+            let (x, y) = after.position();
+            let empty = map.at(x as i32, y as i32) == Some(MapPosition::Empty);
+
+            if seen.insert(after.position()) && empty {
+                candidates.push(ObstacleCandidate {
+                    position: after.position(),
+                    guard: before,
+                });
+            }
+        }
+
+        match event {
+            SimulationEvent::Moved(after) | SimulationEvent::Turned(after) => before = after,
+            SimulationEvent::Exited(_) | SimulationEvent::LoopDetected(_) => {}
+        }
+    }
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small xorshift to generate maps, keeps test deterministic without extra dependency
+    fn generate_map(width: usize, height: usize, mut seed: u64) -> String {
+        let mut rows = Vec::with_capacity(height);
+        for y in 0..height {
+            let row = (0..width)
+                .map(|x| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+
+                    if (x, y) == (width / 2, height / 2) {
+                        return '^';
+                    }
+
+                    match seed % 20 {
+                        0 | 1 => '#',
+                        2 => 'T',
+                        3 => 'N',
+                        4 => 'S',
+                        5 => 'W',
+                        6 => 'E',
+                        _ => '.',
+                    }
+                })
+                .collect::<String>();
+            rows.push(row);
+        }
+
+        rows.join("\n")
+    }
+
+    #[test]
+    fn jump_table_matches_brute_force() {
+        for seed in 1..50 {
+            let input = generate_map(24, 17, seed);
+            let map = input.parse::<Map>().unwrap();
+            let guard = map.single_guard().unwrap();
+
+            let jumps = JumpTable::new(&map);
+            let mut visited = VisitedStates::new(&jumps);
+
+            let candidates = match obstacle_candidates(guard, &map, None) {
+                Ok(candidates) => candidates,
+                Err(Day06Error::GuardEnclosedError { .. }) => continue,
+                Err(e) => panic!("unexpected simulation error {e}"),
+            };
+
+            for candidate in candidates {
+                let obstacle = candidate.position;
+                let events = Simulation::new(guard, &map).with_extra_obstacle(obstacle);
+
+                // one-way tiles can lock guard in cell, both must report it at same position
+                let brute_force = match events.last().unwrap() {
+                    Ok(event) => Ok(matches!(event, SimulationEvent::LoopDetected(_))),
+                    Err(Day06Error::GuardEnclosedError { position, .. }) => Err(position),
+                    Err(e) => panic!("unexpected simulation error {e}"),
+                };
+                let fast = match find_loop(&jumps, &mut visited, candidate.guard, obstacle) {
+                    Ok(Some(entry)) => {
+                        let found = LoopObstacle {
+                            position: obstacle,
+                            entry,
+                        };
+                        let cycle = found.cycle(&map).unwrap();
+                        assert_eq!(Some(&entry), cycle.first());
+
+                        Ok(true)
+                    }
+                    Ok(None) => Ok(false),
+                    Err(Day06Error::GuardEnclosedError { position, .. }) => Err(position),
+                    Err(e) => panic!("unexpected jump table error {e}"),
+                };
+
+                assert_eq!(brute_force, fast, "seed {seed}, obstacle {obstacle:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn loop_obstacles_of_puzzle_statement() {
+        let map = r"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."
+            .parse::<Map>()
+            .unwrap();
+        let guard = map.single_guard().unwrap();
+
+        let loops = find_loop_obstacles(guard, &map, None).unwrap();
+
+        let mut positions = loops.iter().map(|l| l.position).collect::<Vec<_>>();
+        positions.sort();
+        assert_eq!(
+            vec![(1, 8), (3, 6), (3, 8), (6, 7), (7, 7), (7, 9)],
+            positions
+        );
+
+        // first loop drawn in puzzle text, rectangle from guard start to top right corner
+        let first = loops.iter().find(|l| l.position == (3, 6)).unwrap();
+        let cycle = first.cycle(&map).unwrap();
+        assert_eq!(18, loop_len(&cycle));
+        assert_eq!(22, cycle.len());
+        assert!(cycle.contains(&Guard::new((4, 6), Orientation::Up)));
+    }
+
+    #[test]
+    fn obstacle_sealing_guard_in_is_loop() {
+        // guard starts blocked on three sides, obstacle in front of the open side seals it in
+        let map = "....\n.#..\n#^#.\n....".parse::<Map>().unwrap();
+        let guard = map.single_guard().unwrap();
+
+        let loops = find_loop_obstacles(guard, &map, None).unwrap();
+
+        assert_eq!(
+            vec![(1, 3)],
+            loops.iter().map(|l| l.position).collect::<Vec<_>>()
+        );
+
+        let cycle = loops[0].cycle(&map).unwrap();
+        assert_eq!(4, cycle.len());
+        assert_eq!(0, loop_len(&cycle));
+    }
+
+    #[test]
+    fn loop_entered_by_turn_in_place() {
+        // obstacle at 2x1 is right after corner at 1x1, guard turns twice there and walks down
+        // and back up the same column
+        let map = ".#...\n.....\n.....\n.^...\n#....\n.#..."
+            .parse::<Map>()
+            .unwrap();
+        let guard = map.single_guard().unwrap();
+
+        let loops = find_loop_obstacles(guard, &map, None).unwrap();
+        let corner = loops.iter().find(|l| l.position == (2, 1)).unwrap();
+
+        assert_eq!(Guard::new((1, 1), Orientation::Right), corner.entry);
+
+        let cycle = corner.cycle(&map).unwrap();
+        assert_eq!(6, loop_len(&cycle));
+        assert_eq!(
+            vec![
+                Guard::new((1, 1), Orientation::Right),
+                Guard::new((1, 1), Orientation::Down),
+                Guard::new((1, 2), Orientation::Down),
+                Guard::new((1, 3), Orientation::Down),
+                Guard::new((1, 4), Orientation::Down),
+                Guard::new((1, 4), Orientation::Left),
+                Guard::new((1, 4), Orientation::Up),
+                Guard::new((1, 3), Orientation::Up),
+                Guard::new((1, 2), Orientation::Up),
+                Guard::new((1, 1), Orientation::Up),
+            ],
+            cycle
+        );
+    }
+}
@@ -1,13 +1,21 @@
 mod error;
+mod jump;
 mod models;
+mod render;
 
-use std::{collections::HashSet, time::Instant};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
 
-use advent_of_code_2024::{init, load_day_input};
-use error::Day06Error;
+use advent_of_code_2024::{cli_flag_value, has_cli_flag, init, load_day_input};
 use eyre::Context;
-use models::{simulate_guard_movement, Guard, Map, MapPosition};
+use jump::{find_loop_obstacles, loop_len, LoopObstacle};
+use models::{simulate_patrol, Map, Outcome};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use render::{render_frames, render_route};
 use tracing::info;
 
 fn main() -> eyre::Result<()> {
@@ -16,9 +24,14 @@ fn main() -> eyre::Result<()> {
     info!("loading data...");
     let data = load_day_input("day06.txt")?;
 
+    let max_steps = cli_flag_value("--max-steps")
+        .map(|max_steps| max_steps.parse::<usize>())
+        .transpose()
+        .wrap_err("max steps must be a number")?;
+
     info!("solving part 1...");
     let start = Instant::now();
-    let part_1_res = part1(&data)?;
+    let part_1_res = part1(&data, max_steps)?;
     let end = Instant::now();
     let elapsed = end - start;
     info!(
@@ -28,7 +41,7 @@ fn main() -> eyre::Result<()> {
 
     info!("solving part 2...");
     let start = Instant::now();
-    let part_2_res = part2(&data)?;
+    let part_2_res = part2(&data, max_steps)?;
     let end = Instant::now();
     let elapsed = end - start;
     info!(
@@ -36,73 +49,134 @@ fn main() -> eyre::Result<()> {
         time = elapsed.as_millis()
     );
 
+    if has_cli_flag("--render") {
+        info!("rendering guard route...");
+        let rendered = render(&data, max_steps)?;
+        println!("{rendered}");
+    }
+
+    if has_cli_flag("--loops") {
+        info!("listing obstacles causing loops...");
+        for line in list_loops(&data, max_steps)? {
+            println!("{line}");
+        }
+    }
+
+    if let Some(path) = cli_flag_value("--frames") {
+        info!("writing guard route frames to {path}...");
+        let frames = write_frames(&data, &path, max_steps)?;
+        info!("written {frames} frames");
+    }
+
     Ok(())
 }
 
-fn part1(data: &str) -> eyre::Result<usize> {
+fn part1(data: &str, max_steps: Option<usize>) -> eyre::Result<usize> {
     info!("parsing map...");
     let map = data.parse::<Map>().wrap_err("failed to parse map")?;
-
-    let guard = Guard::new(map.guard_starting_position(), models::Orientation::Up);
-    info!("found guard at {:?}", guard);
+    info!("found guards {:?}", map.guards());
 
     info!("simulating guard movements...");
-    let steps =
-        simulate_guard_movement(guard, map).wrap_err("failed to simualte guard movements")?;
+    let patrol = simulate_patrol(&map, max_steps).wrap_err("failed to simualte guard movements")?;
 
-    let distinct = steps.into_iter().collect::<HashSet<_>>();
+    for (idx, outcome) in patrol.outcomes.iter().enumerate() {
+        if let Outcome::Looped(guard) = outcome {
+            info!("guard {idx} never leaves the map, loops at {:?}", guard);
+        }
+    }
+    for collision in &patrol.collisions {
+        info!(
+            "guards {} and {} collide at {}x{} after {} steps",
+            collision.guards.0,
+            collision.guards.1,
+            collision.position.0,
+            collision.position.1,
+            collision.step
+        );
+    }
+
+    let distinct = patrol.routes.into_iter().flatten().collect::<HashSet<_>>();
 
     Ok(distinct.len())
 }
 
-fn part2(data: &str) -> eyre::Result<usize> {
+fn part2(data: &str, max_steps: Option<usize>) -> eyre::Result<usize> {
     info!("parsing map...");
     let map = data.parse::<Map>().wrap_err("failed to parse map")?;
 
-    let guard = Guard::new(map.guard_starting_position(), models::Orientation::Up);
-    info!("found guard at {:?}", guard);
+    let loops = loop_obstacles(&map, max_steps)?;
 
-    info!("prepare map candidates with new obstacle placed...");
-    let maps = prepare_multi_map(&map);
+    Ok(loops.len())
+}
 
-    info!("simulating guard movements to find loops...");
-    let loops = maps
-        .into_par_iter()
-        .filter_map(|m| is_looped(m, guard).then_some(()))
-        .count();
+/// Obstacles which make single guard of map patrol forever
+fn loop_obstacles(map: &Map, max_steps: Option<usize>) -> eyre::Result<Vec<LoopObstacle>> {
+    let guard = map
+        .single_guard()
+        .wrap_err("loops can be searched only for single guard")?;
+    info!("found guard at {:?}", guard);
+
+    info!("simulating guard movements with obstacles on its route to find loops...");
+    let loops = find_loop_obstacles(guard, map, max_steps)
+        .wrap_err("failed to simulate guard movements")?;
 
     Ok(loops)
 }
 
-fn is_looped(map: Map, guard: Guard) -> bool {
-    match simulate_guard_movement(guard, map) {
-        Ok(_) => false,
-        Err(Day06Error::SimulationLoopError) => true,
-        _ => panic!("unexpected simulation result"),
-    }
-}
+fn list_loops(data: &str, max_steps: Option<usize>) -> eyre::Result<Vec<String>> {
+    let map = data.parse::<Map>().wrap_err("failed to parse map")?;
 
-fn prepare_multi_map(original: &Map) -> Vec<Map> {
-    let guard_start = original.guard_starting_position();
-    let (width, height) = original.dimension();
-
-    (0..width)
-        .flat_map(|x| (0..height).map(move |y| (x, y)))
-        .filter(|(x, y)| {
-            (*x, *y) != guard_start
-                && original
-                    .at(*x as i32, *y as i32)
-                    .expect("must be valid map position")
-                    == MapPosition::Empty
-        })
-        .map(|(x, y)| {
-            let mut new = original.clone();
-            new.set_at_position(x, y, MapPosition::Obstacle);
-            new
+    loop_obstacles(&map, max_steps)?
+        .into_par_iter()
+        .map(|l| {
+            let cycle = l.cycle(&map).wrap_err("failed to trace loop")?;
+
+            Ok(format!(
+                "obstacle at {}x{}: loop of {} steps through {} states entered at {}x{} facing {:?}",
+                l.position.0,
+                l.position.1,
+                loop_len(&cycle),
+                cycle.len(),
+                l.entry.position().0,
+                l.entry.position().1,
+                l.entry.facing()
+            ))
         })
         .collect()
 }
 
+fn render(data: &str, max_steps: Option<usize>) -> eyre::Result<String> {
+    let map = data.parse::<Map>().wrap_err("failed to parse map")?;
+
+    // obstacles can be searched only for single guard, route is drawn for any map
+    let obstacles = if map.guards().len() == 1 {
+        loop_obstacles(&map, max_steps)?
+            .into_iter()
+            .map(|l| l.position)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let rendered =
+        render_route(&map, obstacles, max_steps).wrap_err("failed to simulate guard movements")?;
+    Ok(rendered)
+}
+
+fn write_frames(data: &str, path: &str, max_steps: Option<usize>) -> eyre::Result<usize> {
+    let map = data.parse::<Map>().wrap_err("failed to parse map")?;
+
+    let mut out = BufWriter::new(
+        File::create(path).wrap_err_with(|| format!("failed to create frames file {path}"))?,
+    );
+    let frames = render_frames(&map, max_steps, &mut out)
+        .wrap_err_with(|| format!("failed to render frames to {path}"))?;
+    out.flush()
+        .wrap_err_with(|| format!("failed to write frames to {path}"))?;
+
+    Ok(frames)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,8 +194,15 @@ mod tests {
 
     #[test]
     fn part_1_sample_data() {
-        let res = part1(SAMPLE).expect("part 1 not to error on sample data");
+        let res = part1(SAMPLE, None).expect("part 1 not to error on sample data");
 
         assert_eq!(41, res);
     }
+
+    #[test]
+    fn part_2_sample_data() {
+        let res = part2(SAMPLE, None).expect("part 2 not to error on sample data");
+
+        assert_eq!(6, res);
+    }
 }
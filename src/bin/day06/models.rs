@@ -13,27 +13,101 @@ pub(crate) enum Orientation {
     Right,
 }
 
+impl Orientation {
+    pub(crate) const ALL: [Orientation; 4] = [
+        Orientation::Up,
+        Orientation::Down,
+        Orientation::Left,
+        Orientation::Right,
+    ];
+
+    pub(crate) fn turned(self, turn: Turn) -> Orientation {
+        match (turn, self) {
+            (Turn::Right, Orientation::Up) | (Turn::Left, Orientation::Down) => Orientation::Right,
+            (Turn::Right, Orientation::Down) | (Turn::Left, Orientation::Up) => Orientation::Left,
+            (Turn::Right, Orientation::Left) | (Turn::Left, Orientation::Right) => Orientation::Up,
+            (Turn::Right, Orientation::Right) | (Turn::Left, Orientation::Left) => {
+                Orientation::Down
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Turn {
+    Right,
+    Left,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum MapPosition {
     Empty,
+    /// Guard turns right in front of it
     Obstacle,
+    /// Guard turns left in front of it
+    TurnLeft,
+    /// Can be entered only when moving in given direction, blocks like `Obstacle` otherwise
+    OneWay(Orientation),
+}
+
+/// What guard facing some direction does when tile is right in front of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Reaction {
+    Step,
+    Turn(Turn),
+}
+
+impl MapPosition {
+    pub(crate) fn reaction(self, facing: Orientation) -> Reaction {
+        match self {
+            MapPosition::Empty => Reaction::Step,
+            MapPosition::OneWay(allowed) if allowed == facing => Reaction::Step,
+            MapPosition::Obstacle | MapPosition::OneWay(_) => Reaction::Turn(Turn::Right),
+            MapPosition::TurnLeft => Reaction::Turn(Turn::Left),
+        }
+    }
 }
 
+/// Meaning of symbol in map input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MapSymbol {
+    Tile(MapPosition),
+    /// Guard starting position, guard stands on empty tile
+    Guard(Orientation),
+}
+
+/// Every symbol known in map input, new tile kind needs entry here and its `reaction`
+pub(crate) const TILE_TABLE: &[(char, MapSymbol)] = &[
+    ('.', MapSymbol::Tile(MapPosition::Empty)),
+    ('#', MapSymbol::Tile(MapPosition::Obstacle)),
+    ('T', MapSymbol::Tile(MapPosition::TurnLeft)),
+    ('N', MapSymbol::Tile(MapPosition::OneWay(Orientation::Up))),
+    ('S', MapSymbol::Tile(MapPosition::OneWay(Orientation::Down))),
+    ('W', MapSymbol::Tile(MapPosition::OneWay(Orientation::Left))),
+    (
+        'E',
+        MapSymbol::Tile(MapPosition::OneWay(Orientation::Right)),
+    ),
+    ('^', MapSymbol::Guard(Orientation::Up)),
+    ('v', MapSymbol::Guard(Orientation::Down)),
+    ('<', MapSymbol::Guard(Orientation::Left)),
+    ('>', MapSymbol::Guard(Orientation::Right)),
+];
+
 #[derive(Clone)]
 pub(crate) struct Map {
     content: Vec<Vec<MapPosition>>,
-    guard_starting_position: (usize, usize),
+    guards: Vec<Guard>,
 }
 
 impl Debug for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "map {}x{} guard @ {}x{}",
+            "map {}x{} with {} guards",
             self.content[0].len(),
             self.content.len(),
-            self.guard_starting_position.0,
-            self.guard_starting_position.1
+            self.guards.len()
         )
     }
 }
@@ -42,38 +116,26 @@ impl FromStr for Map {
     type Err = Day06Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const EMPTY_CHAR: char = '.';
-        const OBSTACLE_CHAR: char = '#';
-        const GUARD_CHAR: char = '^';
-
-        let mut guard_pos: Option<(usize, usize)> = None;
+        let mut guards: Vec<Guard> = Vec::new();
         let mut content: Vec<Vec<MapPosition>> = Vec::with_capacity(1 << 10);
 
         for (row, line) in s.lines().enumerate() {
             let mut row_content = Vec::with_capacity(line.len());
 
             for (col, sym) in line.chars().enumerate() {
-                match sym {
-                    EMPTY_CHAR => row_content.push(MapPosition::Empty),
-                    OBSTACLE_CHAR => row_content.push(MapPosition::Obstacle),
-                    GUARD_CHAR => {
-                        if let Some(guard) = guard_pos {
-                            return Err(Day06Error::GuardDoubleDefinedError {
-                                first_post: guard,
-                                second_pos: (col, row),
-                            });
-                        }
-
-                        guard_pos = Some((col, row));
-
+                let Some((_, symbol)) = TILE_TABLE.iter().find(|(c, _)| *c == sym) else {
+                    return Err(Day06Error::MapParseError {
+                        input: sym.to_string(),
+                        error_msg: format!("unkown symbol at ({col},{row})"),
+                    });
+                };
+
+                match symbol {
+                    MapSymbol::Tile(tile) => row_content.push(*tile),
+                    MapSymbol::Guard(facing) => {
+                        guards.push(Guard::new((col, row), *facing));
                         row_content.push(MapPosition::Empty);
                     }
-                    _ => {
-                        return Err(Day06Error::MapParseError {
-                            input: sym.to_string(),
-                            error_msg: "unkown symbol".to_string(),
-                        })
-                    }
                 }
             }
 
@@ -81,21 +143,18 @@ impl FromStr for Map {
             content.push(row_content);
         }
 
-        let Some(guard_pos) = guard_pos else {
+        if guards.is_empty() {
             return Err(Day06Error::GuardMissingInitialPosition);
-        };
+        }
 
         content.shrink_to_fit();
-        Ok(Map::new(content, guard_pos))
+        Ok(Map::new(content, guards))
     }
 }
 
 impl Map {
-    fn new(content: Vec<Vec<MapPosition>>, guard_starting_position: (usize, usize)) -> Self {
-        Self {
-            content,
-            guard_starting_position,
-        }
+    fn new(content: Vec<Vec<MapPosition>>, guards: Vec<Guard>) -> Self {
+        Self { content, guards }
     }
 
     pub(crate) fn at(&self, x: i32, y: i32) -> Option<MapPosition> {
@@ -106,10 +165,6 @@ impl Map {
         self.content.get(y).and_then(|row| row.get(x)).cloned()
     }
 
-    pub(crate) fn set_at_position(&mut self, x: usize, y: usize, new_val: MapPosition) {
-        self.content[y][x] = new_val;
-    }
-
     pub(crate) fn dimension(&self) -> (usize, usize) {
         let width = self.content[0].len();
         let height = self.content.len();
@@ -117,12 +172,25 @@ impl Map {
         (width, height)
     }
 
-    pub(crate) fn guard_starting_position(&self) -> (usize, usize) {
-        self.guard_starting_position
+    /// Guards in order they appear in map input, row by row
+    pub(crate) fn guards(&self) -> &[Guard] {
+        &self.guards
+    }
+
+    /// The only guard on map, error if there are more of them
+    pub(crate) fn single_guard(&self) -> Result<Guard, Day06Error> {
+        match self.guards.as_slice() {
+            [guard] => Ok(*guard),
+            [first, second, ..] => Err(Day06Error::GuardDoubleDefinedError {
+                first_post: first.position,
+                second_pos: second.position,
+            }),
+            [] => Err(Day06Error::GuardMissingInitialPosition),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Guard {
     position: (usize, usize),
     facing: Orientation,
@@ -133,6 +201,14 @@ impl Guard {
         Self { position, facing }
     }
 
+    pub(crate) fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    pub(crate) fn facing(&self) -> Orientation {
+        self.facing
+    }
+
     fn next_step(&self) -> (i32, i32) {
         let x = self.position.0 as i32;
         let y = self.position.1 as i32;
@@ -160,15 +236,8 @@ impl Guard {
         Ok(())
     }
 
-    fn rotate(&mut self) {
-        let next = match self.facing {
-            Orientation::Up => Orientation::Right,
-            Orientation::Down => Orientation::Left,
-            Orientation::Left => Orientation::Up,
-            Orientation::Right => Orientation::Down,
-        };
-
-        self.facing = next;
+    fn rotate(&mut self, turn: Turn) {
+        self.facing = self.facing.turned(turn);
     }
 }
 
@@ -181,32 +250,107 @@ impl From<&Guard> for LoopDetectionPosition {
     }
 }
 
-pub(crate) fn simulate_guard_movement(
-    mut guard: Guard,
-    map: Map,
-) -> Result<Vec<(usize, usize)>, Day06Error> {
-    let mut step_seq = Vec::with_capacity(1 << 12);
-    step_seq.push(guard.position);
+/// Single step of guard simulation, every event carries guard state after the step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SimulationEvent {
+    /// Guard stepped onto next position
+    Moved(Guard),
+    /// Guard turned in place according to tile reaction
+    Turned(Guard),
+    /// Next step would take guard out of map, last event of simulation
+    Exited(Guard),
+    /// Guard got into state it was already in, either by step or by turn in place. Last event of
+    /// simulation.
+    LoopDetected(Guard),
+}
 
-    let mut loop_detector: FxHashSet<LoopDetectionPosition> = FxHashSet::default();
-    loop_detector.insert((&guard).into());
+/// How guard patrol ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// Guard left the map, holds guard state on last position inside map
+    Exited(Guard),
+    /// Guard will patrol forever, holds first repeated guard state
+    Looped(Guard),
+}
 
-    let mut rotation_count = 0;
-    loop {
-        if rotation_count >= 3 {
-            return Err(Day06Error::MovementSimulationError {
-                why: format!("rotated {rotation_count} without making step. spinning in circle"),
-            });
+/// Iterator over guard movement on map, ends after `Exited` or `LoopDetected` event or first
+/// error
+pub(crate) struct Simulation<'a> {
+    map: &'a Map,
+    guard: Guard,
+    loop_detector: FxHashSet<LoopDetectionPosition>,
+    rotation_count: usize,
+    steps: usize,
+    max_steps: Option<usize>,
+    /// Obstacle which is not part of map
+    extra_obstacle: Option<(usize, usize)>,
+    finished: bool,
+}
+
+impl<'a> Simulation<'a> {
+    pub(crate) fn new(guard: Guard, map: &'a Map) -> Self {
+        let mut loop_detector: FxHashSet<LoopDetectionPosition> = FxHashSet::default();
+        loop_detector.insert((&guard).into());
+
+        Self {
+            map,
+            guard,
+            loop_detector,
+            rotation_count: 0,
+            steps: 0,
+            max_steps: None,
+            extra_obstacle: None,
+            finished: false,
+        }
+    }
+
+    /// Stops simulation with error once guard makes more than `max_steps` steps, turning in
+    /// place is not counted as step. `None` lets simulation run until exit or loop.
+    pub(crate) fn with_max_steps(mut self, max_steps: Option<usize>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Simulates guard as if `Obstacle` was placed at `position`, map is left untouched
+    pub(crate) fn with_extra_obstacle(mut self, position: (usize, usize)) -> Self {
+        self.extra_obstacle = Some(position);
+        self
+    }
+
+    /// Tile in front of guard standing at `position` facing `facing`, `None` outside of map
+    fn tile_ahead(&self, position: (usize, usize), facing: Orientation) -> Option<MapPosition> {
+        let (x, y) = Guard::new(position, facing).next_step();
+
+        let is_extra_obstacle = self
+            .extra_obstacle
+            .is_some_and(|(ox, oy)| (ox as i32, oy as i32) == (x, y));
+        match self.map.at(x, y) {
+            Some(_) if is_extra_obstacle => Some(MapPosition::Obstacle),
+            content => content,
         }
+    }
+
+    /// Guard at `position` can not step in any direction because of map tiles alone, tiles at map
+    /// edge let guard exit. Guard sealed in by extra obstacle is not enclosed, it patrols forever
+    /// in place and that is a loop.
+    fn is_enclosed(&self, position: (usize, usize)) -> bool {
+        Orientation::ALL.into_iter().all(|facing| {
+            let (x, y) = Guard::new(position, facing).next_step();
+            self.map
+                .at(x, y)
+                .is_some_and(|tile| tile.reaction(facing) != Reaction::Step)
+        })
+    }
 
-        let next_planned_step = guard.next_step();
+    fn step(&mut self) -> Result<SimulationEvent, Day06Error> {
+        let next_planned_step = self.guard.next_step();
 
-        let Some(next_content) = map.at(next_planned_step.0, next_planned_step.1) else {
+        let Some(next_content) = self.tile_ahead(self.guard.position, self.guard.facing) else {
             debug!(
                 "next step will take us out of map! guard = {:?}; step = {}x{}",
-                guard, next_planned_step.0, next_planned_step.1
+                self.guard, next_planned_step.0, next_planned_step.1
             );
-            break;
+            return Ok(SimulationEvent::Exited(self.guard));
         };
 
         debug!(
@@ -214,28 +358,457 @@ pub(crate) fn simulate_guard_movement(
             next_planned_step.0, next_planned_step.1
         );
 
-        match next_content {
-            MapPosition::Empty => {
-                guard.make_step()?;
-                debug!("moved to {}x{}", guard.position.0, guard.position.1);
-                step_seq.push(guard.position);
+        match next_content.reaction(self.guard.facing) {
+            Reaction::Step => {
+                if let Some(max_steps) = self.max_steps.filter(|max| self.steps >= *max) {
+                    return Err(Day06Error::StepBudgetExceededError { max_steps });
+                }
 
-                if !loop_detector.insert((&guard).into()) {
-                    return Err(Day06Error::SimulationLoopError);
+                self.guard.make_step()?;
+                self.steps += 1;
+                debug!(
+                    "moved to {}x{}",
+                    self.guard.position.0, self.guard.position.1
+                );
+                self.rotation_count = 0;
+
+                if !self.loop_detector.insert((&self.guard).into()) {
+                    return Ok(SimulationEvent::LoopDetected(self.guard));
                 }
 
-                rotation_count = 0;
+                Ok(SimulationEvent::Moved(self.guard))
             }
-            MapPosition::Obstacle => {
+            Reaction::Turn(turn) => {
                 debug!(
                     "obstalce @ {}x{}; facing {:?}",
-                    next_planned_step.0, next_planned_step.1, guard.facing
+                    next_planned_step.0, next_planned_step.1, self.guard.facing
                 );
-                guard.rotate();
-                rotation_count += 1;
+                self.guard.rotate(turn);
+                self.rotation_count += 1;
+
+                // states after turns are recorded too, guard spinning in place between tiles
+                // turning it opposite ways repeats state without making step
+                if !self.loop_detector.insert((&self.guard).into()) {
+                    if self.is_enclosed(self.guard.position) {
+                        return Err(Day06Error::GuardEnclosedError {
+                            position: self.guard.position,
+                            turns: self.rotation_count,
+                        });
+                    }
+
+                    return Ok(SimulationEvent::LoopDetected(self.guard));
+                }
+
+                Ok(SimulationEvent::Turned(self.guard))
             }
-        };
+        }
+    }
+}
+
+impl Iterator for Simulation<'_> {
+    type Item = Result<SimulationEvent, Day06Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let event = self.step();
+        self.finished = !matches!(
+            event,
+            Ok(SimulationEvent::Moved(_) | SimulationEvent::Turned(_))
+        );
+
+        Some(event)
+    }
+}
+
+/// Two guards standing on same position after same number of steps, or swapping positions
+/// during one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Collision {
+    /// Number of steps made by guards, turning in place does not take a step
+    pub(crate) step: usize,
+    pub(crate) position: (usize, usize),
+    /// Indexes of guards into `Map::guards`
+    pub(crate) guards: (usize, usize),
+}
+
+/// Result of patrolling all guards of map together
+#[derive(Debug)]
+pub(crate) struct Patrol {
+    /// Positions visited by each guard in order, starting position included. Revisited positions
+    /// are listed again.
+    pub(crate) routes: Vec<Vec<(usize, usize)>>,
+    pub(crate) outcomes: Vec<Outcome>,
+    pub(crate) collisions: Vec<Collision>,
+}
+
+/// Moves all guards of map one step at a time. Guards do not block each other, collisions are
+/// only reported. Each guard is followed until it leaves map or its loop is detected, looping
+/// guards keep walking their loop until every other guard finishes so collisions with them are
+/// found too.
+pub(crate) fn simulate_patrol(map: &Map, max_steps: Option<usize>) -> Result<Patrol, Day06Error> {
+    let mut simulations = map
+        .guards()
+        .iter()
+        .map(|guard| Simulation::new(*guard, map).with_max_steps(max_steps))
+        .collect::<Vec<_>>();
+
+    let mut routes = map
+        .guards()
+        .iter()
+        .map(|guard| vec![guard.position])
+        .collect::<Vec<_>>();
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; simulations.len()];
+    // position after every step of guards still on map, looping ones included
+    let mut tracks = routes.clone();
+    let mut cycles: Vec<Option<LoopCycle>> = vec![None; simulations.len()];
+    let mut collisions = Vec::new();
+
+    for step in 1.. {
+        if outcomes.iter().all(Option::is_some) {
+            break;
+        }
+
+        let mut on_map = Vec::with_capacity(simulations.len());
+
+        for (idx, simulation) in simulations.iter_mut().enumerate() {
+            if let Some(cycle) = cycles[idx].as_mut() {
+                tracks[idx].push(cycle.advance());
+                on_map.push(idx);
+                continue;
+            }
+
+            if outcomes[idx].is_some() {
+                continue;
+            }
+
+            // turns are made in place, so guard is advanced until it steps or finishes
+            for event in simulation.by_ref() {
+                match event? {
+                    SimulationEvent::Turned(_) => continue,
+                    SimulationEvent::Moved(guard) => {
+                        routes[idx].push(guard.position);
+                        tracks[idx].push(guard.position);
+                        on_map.push(idx);
+                    }
+                    SimulationEvent::Exited(guard) => outcomes[idx] = Some(Outcome::Exited(guard)),
+                    SimulationEvent::LoopDetected(guard) => {
+                        let mut cycle = LoopCycle::new(guard, map)?;
+
+                        // loop closed by step is move of this step, loop closed by turn in place
+                        // moves along the loop right away and does not add position to route
+                        if tracks[idx].last() != Some(&guard.position) {
+                            routes[idx].push(guard.position);
+                            tracks[idx].push(guard.position);
+                        } else {
+                            tracks[idx].push(cycle.advance());
+                        }
+                        on_map.push(idx);
+
+                        outcomes[idx] = Some(Outcome::Looped(guard));
+                        cycles[idx] = Some(cycle);
+                    }
+                }
+                break;
+            }
+        }
+
+        collisions.extend(find_collisions(&tracks, &on_map, step));
+    }
+
+    let outcomes = outcomes
+        .into_iter()
+        .map(|outcome| outcome.expect("all guards finished"))
+        .collect();
+
+    Ok(Patrol {
+        routes,
+        outcomes,
+        collisions,
+    })
+}
+
+/// Positions of one round of guard loop, guard spinning in place has none and stays put
+#[derive(Debug, Clone)]
+struct LoopCycle {
+    start: (usize, usize),
+    positions: Vec<(usize, usize)>,
+    next: usize,
+}
+
+impl LoopCycle {
+    /// Replays loop from `entry`, first repeated guard state, until it closes again
+    fn new(entry: Guard, map: &Map) -> Result<Self, Day06Error> {
+        let mut positions = Vec::new();
+
+        for event in Simulation::new(entry, map) {
+            match event? {
+                SimulationEvent::Moved(guard) => positions.push(guard.position),
+                // loop closed by step ends with move back to entry
+                SimulationEvent::LoopDetected(guard)
+                    if positions.last().copied().unwrap_or(entry.position) != guard.position =>
+                {
+                    positions.push(guard.position)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            start: entry.position,
+            positions,
+            next: 0,
+        })
+    }
+
+    /// Position after next step along the loop
+    fn advance(&mut self) -> (usize, usize) {
+        if self.positions.is_empty() {
+            return self.start;
+        }
+
+        let position = self.positions[self.next % self.positions.len()];
+        self.next += 1;
+        position
+    }
+}
+
+/// Collisions of guards in `on_map` with each other during `step`
+fn find_collisions(
+    tracks: &[Vec<(usize, usize)>],
+    on_map: &[usize],
+    step: usize,
+) -> Vec<Collision> {
+    let mut collisions = Vec::new();
+
+    // guards on map in this step were on map in every previous one too, so track index is step
+    let at = |idx: usize, step: usize| tracks[idx][step];
+
+    for (i, &lhs) in on_map.iter().enumerate() {
+        for &rhs in &on_map[i + 1..] {
+            let same_cell = at(lhs, step) == at(rhs, step);
+            let swapped = at(lhs, step) == at(rhs, step - 1) && at(rhs, step) == at(lhs, step - 1);
+
+            if same_cell || swapped {
+                collisions.push(Collision {
+                    step,
+                    position: at(lhs, step),
+                    guards: (lhs, rhs),
+                });
+            }
+        }
+    }
+
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_stop_at_exit() {
+        let map = ".#.\n...\n.^.".parse::<Map>().unwrap();
+        let guard = map.single_guard().unwrap();
+
+        let events = Simulation::new(guard, &map)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                SimulationEvent::Moved(Guard::new((1, 1), Orientation::Up)),
+                SimulationEvent::Turned(Guard::new((1, 1), Orientation::Right)),
+                SimulationEvent::Moved(Guard::new((2, 1), Orientation::Right)),
+                SimulationEvent::Exited(Guard::new((2, 1), Orientation::Right)),
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn loop_is_outcome_not_error() {
+        let map = ".#..\n...#\n#^..\n..#.".parse::<Map>().unwrap();
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert!(matches!(patrol.outcomes[0], Outcome::Looped(_)));
     }
 
-    Ok(step_seq)
+    #[test]
+    fn guards_start_in_marked_orientation() {
+        let map = "^.v\n<.>".parse::<Map>().unwrap();
+
+        let facing = map.guards().iter().map(|g| g.facing).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                Orientation::Up,
+                Orientation::Down,
+                Orientation::Left,
+                Orientation::Right
+            ],
+            facing
+        );
+        assert!(matches!(
+            map.single_guard(),
+            Err(Day06Error::GuardDoubleDefinedError { .. })
+        ));
+    }
+
+    #[test]
+    fn turn_left_and_one_way_tiles() {
+        // guard can not enter `W` going up, so turns right and passes `E`
+        let map = ".T..\n....\n.W..\n.^E.".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(vec![(1, 3), (2, 3), (3, 3)], patrol.routes[0]);
+        assert_eq!(
+            Outcome::Exited(Guard::new((3, 3), Orientation::Right)),
+            patrol.outcomes[0]
+        );
+
+        // guard turns left in front of `T`
+        let map = ".T..\n....\n.^..".parse::<Map>().unwrap();
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(vec![(1, 2), (1, 1), (0, 1)], patrol.routes[0]);
+    }
+
+    #[test]
+    fn colliding_guards_are_reported() {
+        let map = ">...<\n.....\nv....".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(
+            vec![Collision {
+                step: 2,
+                position: (2, 0),
+                guards: (0, 1)
+            }],
+            patrol.collisions
+        );
+        assert_eq!(1, patrol.routes[2].len());
+
+        let map = ">..<".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(
+            vec![Collision {
+                step: 2,
+                position: (2, 0),
+                guards: (0, 1)
+            }],
+            patrol.collisions
+        );
+    }
+
+    #[test]
+    fn collisions_with_looping_guards_are_reported() {
+        // guard 1 spins at 2x1 forever, guard 0 walks onto it and starts spinning there too
+        let map = "..#..\n>.^T.\n.....".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(
+            vec![
+                Collision {
+                    step: 2,
+                    position: (2, 1),
+                    guards: (0, 1)
+                },
+                Collision {
+                    step: 3,
+                    position: (2, 1),
+                    guards: (0, 1)
+                },
+            ],
+            patrol.collisions
+        );
+
+        // guard 1 closes its loop at step 9 and meets guard 0 on second round of it
+        let map = [
+            ".#...............",
+            "....#............",
+            "................<",
+            "#^...............",
+            "...#.............",
+        ]
+        .join("\n")
+        .parse::<Map>()
+        .unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(
+            Outcome::Looped(Guard::new((1, 3), Orientation::Up)),
+            patrol.outcomes[1]
+        );
+        assert_eq!(
+            vec![Collision {
+                step: 13,
+                position: (3, 2),
+                guards: (0, 1)
+            }],
+            patrol.collisions
+        );
+    }
+
+    #[test]
+    fn guard_boxed_on_three_sides_turns_to_open_side() {
+        let map = "###\n.^#\n###".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(vec![(1, 1), (0, 1)], patrol.routes[0]);
+        assert_eq!(
+            Outcome::Exited(Guard::new((0, 1), Orientation::Left)),
+            patrol.outcomes[0]
+        );
+    }
+
+    #[test]
+    fn enclosed_guard_is_error() {
+        let map = "###\n#^#\n###".parse::<Map>().unwrap();
+
+        let res = simulate_patrol(&map, None);
+
+        assert!(matches!(
+            res,
+            Err(Day06Error::GuardEnclosedError {
+                position: (1, 1),
+                turns: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn step_budget_is_enforced() {
+        let map = "....\n....\n...^".parse::<Map>().unwrap();
+
+        assert!(simulate_patrol(&map, Some(2)).is_ok());
+        assert!(matches!(
+            simulate_patrol(&map, Some(1)),
+            Err(Day06Error::StepBudgetExceededError { max_steps: 1 })
+        ));
+    }
+
+    #[test]
+    fn spinning_guard_loops() {
+        // guard turns right in front of `#` and left in front of `T` forever, down and left
+        // neighbours are free so it is not enclosed
+        let map = ".....\n..#..\n..^T.\n.....".parse::<Map>().unwrap();
+
+        let patrol = simulate_patrol(&map, None).unwrap();
+
+        assert_eq!(vec![(2, 2)], patrol.routes[0]);
+        assert_eq!(
+            Outcome::Looped(Guard::new((2, 2), Orientation::Up)),
+            patrol.outcomes[0]
+        );
+    }
 }
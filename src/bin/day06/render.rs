@@ -0,0 +1,254 @@
+use std::io::Write;
+
+use crate::{
+    error::Day06Error,
+    models::{Guard, Map, MapSymbol, Orientation, Simulation, SimulationEvent, TILE_TABLE},
+};
+
+/// Cell walked in vertical direction, drawn as `|`
+const VERTICAL: u8 = 0b01;
+/// Cell walked in horizontal direction, drawn as `-`
+const HORIZONTAL: u8 = 0b10;
+
+/// Draws map together with route walked by guards the way puzzle text does, `|` and `-` for
+/// walked cells, `+` where guard turned or crossed its route and `O` for added obstacles
+pub(crate) struct RouteCanvas<'a> {
+    map: &'a Map,
+    /// `VERTICAL` and `HORIZONTAL` flags for every cell, row by row
+    trails: Vec<u8>,
+    /// Guards drawn on top of route, last recorded state of each guard
+    guards: Vec<Guard>,
+    obstacles: Vec<(usize, usize)>,
+}
+
+impl<'a> RouteCanvas<'a> {
+    pub(crate) fn new(map: &'a Map) -> Self {
+        let (width, height) = map.dimension();
+
+        Self {
+            map,
+            trails: vec![0; width * height],
+            guards: map.guards().to_vec(),
+            obstacles: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_obstacles(mut self, obstacles: Vec<(usize, usize)>) -> Self {
+        self.obstacles = obstacles;
+        self
+    }
+
+    /// Marks route of guard with index `guard_idx` into `Map::guards` made by `event`
+    pub(crate) fn record(&mut self, guard_idx: usize, event: &SimulationEvent) {
+        let before = self.guards[guard_idx];
+
+        match event {
+            // loop can be closed by turn in place too
+            SimulationEvent::LoopDetected(after) if after.position() == before.position() => {
+                self.mark(after.position(), VERTICAL | HORIZONTAL);
+                self.guards[guard_idx] = *after;
+            }
+            SimulationEvent::Moved(after) | SimulationEvent::LoopDetected(after) => {
+                let axis = match after.facing() {
+                    Orientation::Up | Orientation::Down => VERTICAL,
+                    Orientation::Left | Orientation::Right => HORIZONTAL,
+                };
+
+                self.mark(before.position(), axis);
+                self.mark(after.position(), axis);
+                self.guards[guard_idx] = *after;
+            }
+            SimulationEvent::Turned(after) => {
+                self.mark(after.position(), VERTICAL | HORIZONTAL);
+                self.guards[guard_idx] = *after;
+            }
+            SimulationEvent::Exited(_) => {}
+        }
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let (width, height) = self.map.dimension();
+        let mut rendered = String::with_capacity(height * (width + 1));
+
+        for y in 0..height {
+            for x in 0..width {
+                rendered.push(self.symbol_at(x, y));
+            }
+            rendered.push('\n');
+        }
+
+        rendered
+    }
+
+    fn symbol_at(&self, x: usize, y: usize) -> char {
+        if let Some(guard) = self.guards.iter().find(|g| g.position() == (x, y)) {
+            return symbol(MapSymbol::Guard(guard.facing()));
+        }
+
+        if self.obstacles.contains(&(x, y)) {
+            return 'O';
+        }
+
+        let (width, _) = self.map.dimension();
+        match self.trails[y * width + x] {
+            VERTICAL => '|',
+            HORIZONTAL => '-',
+            0 => {
+                let tile = self.map.at(x as i32, y as i32).expect("valid map position");
+                symbol(MapSymbol::Tile(tile))
+            }
+            _ => '+',
+        }
+    }
+
+    fn mark(&mut self, (x, y): (usize, usize), axis: u8) {
+        let (width, _) = self.map.dimension();
+        self.trails[y * width + x] |= axis;
+    }
+}
+
+fn symbol(map_symbol: MapSymbol) -> char {
+    TILE_TABLE
+        .iter()
+        .find(|(_, s)| *s == map_symbol)
+        .map(|(c, _)| *c)
+        .expect("every map symbol is in tile table")
+}
+
+/// Whole route of all guards with guards drawn at their starting position
+pub(crate) fn render_route(
+    map: &Map,
+    obstacles: Vec<(usize, usize)>,
+    max_steps: Option<usize>,
+) -> Result<String, Day06Error> {
+    let mut canvas = RouteCanvas::new(map).with_obstacles(obstacles);
+
+    for (guard_idx, guard) in map.guards().iter().enumerate() {
+        for event in Simulation::new(*guard, map).with_max_steps(max_steps) {
+            canvas.record(guard_idx, &event?);
+        }
+    }
+
+    // route is recorded guard by guard, so guards are put back to start afterwards
+    canvas.guards = map.guards().to_vec();
+    Ok(canvas.render())
+}
+
+/// Writes frame for initial state and after every simulation event to `out`, each preceded by
+/// `frame <idx>` line. All guards make one event per frame and are drawn at their current
+/// position. Frames are written as they are rendered, returns number of frames.
+pub(crate) fn render_frames(
+    map: &Map,
+    max_steps: Option<usize>,
+    mut out: impl Write,
+) -> Result<usize, Day06Error> {
+    let mut canvas = RouteCanvas::new(map);
+    let mut simulations = map
+        .guards()
+        .iter()
+        .map(|guard| Simulation::new(*guard, map).with_max_steps(max_steps))
+        .collect::<Vec<_>>();
+
+    let mut write_frame = |frame_idx: usize, frame: String| {
+        writeln!(out, "frame {frame_idx}\n{frame}").map_err(|e| Day06Error::FrameWriteError {
+            frame: frame_idx,
+            error_msg: e.to_string(),
+        })
+    };
+
+    write_frame(0, canvas.render())?;
+    let mut frames = 1;
+    loop {
+        let mut recorded = false;
+
+        for (guard_idx, simulation) in simulations.iter_mut().enumerate() {
+            if let Some(event) = simulation.next() {
+                canvas.record(guard_idx, &event?);
+                recorded = true;
+            }
+        }
+
+        if !recorded {
+            break;
+        }
+
+        write_frame(frames, canvas.render())?;
+        frames += 1;
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_loop_from_puzzle_statement() {
+        let map = r"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#.#^.....
+........#.
+#.........
+......#..."
+            .parse::<Map>()
+            .unwrap();
+
+        let rendered = render_route(&map, vec![(3, 6)], None).unwrap();
+
+        assert_eq!(
+            r"....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#...
+",
+            rendered
+        );
+    }
+
+    #[test]
+    fn frames_follow_guard() {
+        let map = ".#.\n...\n.^.".parse::<Map>().unwrap();
+
+        let mut out = Vec::new();
+        let frames = render_frames(&map, None, &mut out).unwrap();
+
+        let expected = [
+            ".#.\n...\n.^.\n",
+            ".#.\n.^.\n.|.\n",
+            ".#.\n.>.\n.|.\n",
+            ".#.\n.+>\n.|.\n",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, frame)| format!("frame {idx}\n{frame}\n"))
+        .collect::<String>();
+        assert!(String::from_utf8(out).unwrap().starts_with(&expected));
+        assert_eq!(5, frames);
+    }
+
+    #[test]
+    fn rendering_respects_step_budget() {
+        let map = "...\n...\n.^.".parse::<Map>().unwrap();
+
+        assert!(render_route(&map, Vec::new(), Some(2)).is_ok());
+        assert!(matches!(
+            render_route(&map, Vec::new(), Some(1)),
+            Err(Day06Error::StepBudgetExceededError { max_steps: 1 })
+        ));
+        assert!(matches!(
+            render_frames(&map, Some(1), std::io::sink()),
+            Err(Day06Error::StepBudgetExceededError { max_steps: 1 })
+        ));
+    }
+}
@@ -1,7 +1,22 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Day07Error {
     #[error("could not parse '{input}' into equation: {error_msg}")]
     EquationParseError { input: String, error_msg: String },
+
+    #[error("could not parse {field} at column {column} of '{input}': {error_msg}")]
+    EquationFieldParseError {
+        input: String,
+        field: String,
+        column: usize,
+        error_msg: String,
+    },
+
+    #[error("unknown operator '{symbol}'")]
+    UnknownOperatorError { symbol: String },
+
+    #[error("sum of test values does not fit, try --wide")]
+    SumOverflowError,
 }
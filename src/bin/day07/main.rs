@@ -1,14 +1,18 @@
 mod error;
 mod models;
+mod operators;
+mod value;
 
 use std::time::Instant;
 
-use advent_of_code_2024::{init, load_day_input};
+use advent_of_code_2024::{cli_flag_value, has_cli_flag, init, load_day_input};
 use error::Day07Error;
 use eyre::Context;
-use models::{Equation, Operator};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use models::{Equation, Evaluation};
+use operators::{parse_operators, Add, Concatenation, Mul, Operator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use tracing::info;
+use value::Value;
 
 fn main() -> eyre::Result<()> {
     init();
@@ -16,9 +20,28 @@ fn main() -> eyre::Result<()> {
     info!("loading data...");
     let data = load_day_input("day07.txt")?;
 
+    match (has_cli_flag("--wide"), has_cli_flag("--signed")) {
+        (false, false) => run::<u64>(&data),
+        (true, false) => {
+            info!("evaluating equations as u128...");
+            run::<u128>(&data)
+        }
+        (false, true) => {
+            info!("evaluating equations as i64...");
+            run::<i64>(&data)
+        }
+        (true, true) => {
+            info!("evaluating equations as i128...");
+            run::<i128>(&data)
+        }
+    }
+}
+
+/// Solves both parts and optional extras with equations evaluated as `V`
+fn run<V: Value>(data: &str) -> eyre::Result<()> {
     info!("solving part 1...");
     let start = Instant::now();
-    let part_1_res = part1(&data)?;
+    let part_1_res = part1::<V>(data)?;
     let end = Instant::now();
     let elapsed = end - start;
     info!(
@@ -28,7 +51,7 @@ fn main() -> eyre::Result<()> {
 
     info!("solving part 2...");
     let start = Instant::now();
-    let part_2_res = part2(&data)?;
+    let part_2_res = part2::<V>(data)?;
     let end = Instant::now();
     let elapsed = end - start;
     info!(
@@ -36,39 +59,113 @@ fn main() -> eyre::Result<()> {
         time = elapsed.as_millis()
     );
 
+    let custom_ops = cli_flag_value("--ops")
+        .map(|symbols| parse_operators::<V>(&symbols))
+        .transpose()
+        .wrap_err("invalid operator list")?;
+    let evaluation = if has_cli_flag("--precedence") {
+        Evaluation::Precedence
+    } else {
+        Evaluation::LeftToRight
+    };
+
+    let part_2_ops = part_2_ops();
+
+    if custom_ops.is_some() || evaluation != Evaluation::LeftToRight {
+        let ops = custom_ops.as_deref().unwrap_or(&part_2_ops);
+
+        info!(
+            "solving with operators {} evaluated {evaluation:?}...",
+            list_ops(ops)
+        );
+        let custom_res = solve(data, ops, evaluation)?;
+        info!("Custom operators solution: {custom_res}");
+    }
+
+    if has_cli_flag("--witnesses") || cli_flag_value("--witnesses").is_some() {
+        let all = cli_flag_value("--witnesses").is_some_and(|v| v == "all");
+        let ops = custom_ops.as_deref().unwrap_or(&part_2_ops);
+
+        info!("listing operators solving equations...");
+        for line in witnesses(data, ops, evaluation, all)? {
+            println!("{line}");
+        }
+    }
+
     Ok(())
 }
 
-fn part1(data: &str) -> eyre::Result<u64> {
+fn part_1_ops<V: Value>() -> [&'static dyn Operator<V>; 2] {
+    [&Add, &Mul]
+}
+
+fn part_2_ops<V: Value>() -> [&'static dyn Operator<V>; 3] {
+    [&Add, &Mul, &Concatenation]
+}
+
+fn part1<V: Value>(data: &str) -> eyre::Result<V> {
+    solve(data, &part_1_ops(), Evaluation::LeftToRight)
+}
+
+fn part2<V: Value>(data: &str) -> eyre::Result<V> {
+    solve(data, &part_2_ops(), Evaluation::LeftToRight)
+}
+
+/// Sum of test values of equations solveable with `ops`
+fn solve<V: Value>(
+    data: &str,
+    ops: &[&dyn Operator<V>],
+    evaluation: Evaluation,
+) -> eyre::Result<V> {
     let equations = load_equations(data).wrap_err("failed to parse equations")?;
 
     let rv = equations
         .into_par_iter()
-        .filter_map(|eq| {
-            eq.is_solveable_with(&[Operator::Add, Operator::Mul])
-                .then_some(eq.test_value())
-        })
-        .sum();
+        .filter(|eq| eq.is_solveable_with(ops, evaluation))
+        .map(|eq| Some(eq.test_value()))
+        .try_reduce(|| V::ZERO, |acc, test_value| acc.checked_add(test_value))
+        .ok_or(Day07Error::SumOverflowError)?;
 
     Ok(rv)
 }
 
-fn part2(data: &str) -> eyre::Result<u64> {
+/// Equations solveable with `ops`, together with first or every solution
+fn witnesses<V: Value>(
+    data: &str,
+    ops: &[&dyn Operator<V>],
+    evaluation: Evaluation,
+    all: bool,
+) -> eyre::Result<Vec<String>> {
     let equations = load_equations(data).wrap_err("failed to parse equations")?;
 
-    let rv = equations
-        .into_par_iter()
-        .filter_map(|eq| {
-            eq.is_solveable_with(&[Operator::Add, Operator::Mul, Operator::Concatenation])
-                .then_some(eq.test_value())
+    let lines = equations
+        .par_iter()
+        .flat_map_iter(|eq| {
+            let solutions = if all {
+                eq.solutions(ops, evaluation)
+            } else {
+                eq.first_solution(ops, evaluation).into_iter().collect()
+            };
+
+            solutions
+                .into_iter()
+                .map(|solution| format!("{}: {solution}", eq.test_value()))
+                .collect::<Vec<_>>()
         })
-        .sum();
+        .collect();
 
-    Ok(rv)
+    Ok(lines)
 }
 
-fn load_equations(data: &str) -> Result<Vec<Equation>, Day07Error> {
-    data.lines().map(|l| l.parse::<Equation>()).collect()
+fn list_ops<V: Value>(ops: &[&dyn Operator<V>]) -> String {
+    ops.iter()
+        .map(|op| op.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn load_equations<V: Value>(data: &str) -> Result<Vec<Equation<V>>, Day07Error> {
+    data.lines().map(|l| l.parse::<Equation<V>>()).collect()
 }
 
 #[cfg(test)]
@@ -87,15 +184,23 @@ mod tests {
 
     #[test]
     fn part_1_sample_data() {
-        let res = part1(SAMPLE).expect("part 1 not to error on sample data");
+        let res = part1::<u64>(SAMPLE).expect("part 1 not to error on sample data");
 
         assert_eq!(3749, res);
     }
 
     #[test]
     fn part_2_sample_data() {
-        let res = part2(SAMPLE).expect("part 2 not to error on sample data");
+        let res = part2::<u64>(SAMPLE).expect("part 2 not to error on sample data");
 
         assert_eq!(11387, res);
     }
+
+    #[test]
+    fn sum_overflow_is_error() {
+        let data = "18446744073709551615: 18446744073709551615\n18446744073709551615: 18446744073709551615";
+
+        assert!(part1::<u64>(data).is_err());
+        assert_eq!(2 * u128::from(u64::MAX), part1::<u128>(data).unwrap());
+    }
 }
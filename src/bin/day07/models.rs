@@ -1,197 +1,627 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::ops::ControlFlow;
 use std::str::FromStr;
 
-use itertools::FoldWhile::{Continue, Done};
-use itertools::Itertools;
 use tracing::debug;
 
 use crate::error::Day07Error;
+use crate::operators::{Inverse, Inverter, Operator};
+use crate::value::Value;
+
+/// Order in which operators of equation are evaluated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) enum Evaluation {
+    /// Strictly left to right regardless of operator, as puzzle defines it
+    #[default]
+    LeftToRight,
+    /// Operators with higher `Operator::precedence` are evaluated first, operators of same
+    /// precedence left to right
+    Precedence,
+}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) enum Operator {
-    Add,
-    Mul,
-    Concatenation,
+pub(crate) struct Equation<V> {
+    test_value: V,
+    operands: Box<[V]>,
 }
 
-impl Operator {
-    fn apply(&self, lhs: u64, rhs: u64) -> u64 {
-        fn num_concat(mut lhs: u64, mut rhs: u64) -> u64 {
-            let mut rhs_digits = Vec::new();
+impl<V: Value> Equation<V> {
+    fn new(test_value: V, operands: Vec<V>) -> Self {
+        let operands = operands.into_boxed_slice();
+        Self {
+            test_value,
+            operands,
+        }
+    }
+
+    pub(crate) fn is_solveable_with(
+        &self,
+        ops: &[&dyn Operator<V>],
+        evaluation: Evaluation,
+    ) -> bool {
+        self.first_solution(ops, evaluation).is_some()
+    }
 
-            while rhs > 0 {
-                let digit = rhs % 10;
-                rhs_digits.push(digit);
-                rhs /= 10;
-            }
+    /// First operator assignment found which produces test value
+    pub(crate) fn first_solution<'a>(
+        &'a self,
+        ops: &[&'a dyn Operator<V>],
+        evaluation: Evaluation,
+    ) -> Option<Solution<'a, V>> {
+        let mut solution = None;
+
+        self.search(ops, evaluation, |operators| {
+            solution = Some(operators);
+            ControlFlow::Break(())
+        });
+
+        solution.map(|operators| Solution::new(self, operators))
+    }
+
+    /// Every operator assignment which produces test value
+    pub(crate) fn solutions<'a>(
+        &'a self,
+        ops: &[&'a dyn Operator<V>],
+        evaluation: Evaluation,
+    ) -> Vec<Solution<'a, V>> {
+        let mut solutions = Vec::new();
+
+        self.search(ops, evaluation, |operators| {
+            solutions.push(Solution::new(self, operators));
+            ControlFlow::Continue(())
+        });
+
+        solutions
+    }
+
+    /// Searches operators right to left when every operator can be inverted and evaluation is
+    /// left to right, branch is abandoned as soon as inversion is impossible. Otherwise operators
+    /// are tried left to right and branch is abandoned when operator can not be applied.
+    fn search<'a>(
+        &self,
+        ops: &[&'a dyn Operator<V>],
+        evaluation: Evaluation,
+        on_solution: impl FnMut(Vec<&'a dyn Operator<V>>) -> ControlFlow<()>,
+    ) {
+        let backwards =
+            evaluation == Evaluation::LeftToRight && ops.iter().all(|op| op.inverse().is_some());
+
+        debug!(
+            "testing solveability for {testing_value} with {operand_count} operands, backwards: {backwards}",
+            testing_value = self.test_value,
+            operand_count = self.operands.len()
+        );
+
+        let chosen = Vec::with_capacity(self.operands.len());
+        if backwards {
+            let inverses = ops
+                .iter()
+                .map(|op| (*op, op.inverse().expect("checked above")))
+                .collect();
+            let mut search = BackwardSearch {
+                ops: inverses,
+                chosen,
+                prefix: Vec::new(),
+                on_solution,
+            };
+            let _ = search.run(self.test_value, &self.operands);
+        } else if let Some((&first, rest)) = self.operands.split_first() {
+            let mut search = ForwardSearch {
+                ops,
+                evaluation,
+                test_value: self.test_value,
+                chosen,
+                on_solution,
+            };
+            let _ = search.run(Vec::new(), first, rest);
+        }
+    }
 
-            for digit in rhs_digits.into_iter().rev() {
-                lhs = lhs * 10 + digit;
+    pub(crate) fn test_value(&self) -> V {
+        self.test_value
+    }
+}
+
+/// Parses `<test value>: <operand> <operand> ...`, fields may be separated by any whitespace and
+/// may carry sign
+impl<V: Value> FromStr for Equation<V> {
+    type Err = Day07Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((test_val, operands)) = s.split_once(':') else {
+            return Err(Day07Error::EquationParseError {
+                input: s.to_owned(),
+                error_msg: "could not split equation into test value and operand list".to_owned(),
+            });
+        };
+
+        // operand list starts right after ':'
+        let operands_column = test_val.chars().count() + 2;
+
+        let test_val = match fields(test_val, 1)[..] {
+            [field] => parse_field(s, "test value", field)?,
+            [] => {
+                return Err(Day07Error::EquationParseError {
+                    input: s.to_owned(),
+                    error_msg: "missing test value".to_owned(),
+                })
             }
-            lhs
+            [_, (column, extra), ..] => {
+                return Err(Day07Error::EquationFieldParseError {
+                    input: s.to_owned(),
+                    field: "test value".to_owned(),
+                    column,
+                    error_msg: format!("unexpected '{extra}' after test value"),
+                })
+            }
+        };
+
+        let operands = fields(operands, operands_column)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, field)| parse_field(s, &format!("operand {}", idx + 1), field))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if operands.is_empty() {
+            return Err(Day07Error::EquationParseError {
+                input: s.to_owned(),
+                error_msg: "operand list is empty".to_owned(),
+            });
         }
 
-        match self {
-            Operator::Add => lhs + rhs,
-            Operator::Mul => lhs * rhs,
-            Operator::Concatenation => num_concat(lhs, rhs),
+        Ok(Self::new(test_val, operands))
+    }
+}
+
+/// Whitespace separated fields of `s` together with column they start at, first character of
+/// `s` is at `column`
+fn fields(s: &str, column: usize) -> Vec<(usize, &str)> {
+    let mut fields = Vec::new();
+    let mut start = None;
+
+    let chars = s.char_indices().chain(std::iter::once((s.len(), ' ')));
+    for (char_idx, (byte_idx, c)) in chars.enumerate() {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some((byte_idx, column + char_idx)),
+            (Some((from, field_column)), true) => {
+                fields.push((field_column, &s[from..byte_idx]));
+                start = None;
+            }
+            _ => {}
         }
     }
+
+    fields
 }
 
-impl Display for Operator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let v = match self {
-            Operator::Add => "+",
-            Operator::Mul => "*",
-            Operator::Concatenation => "||",
+fn parse_field<V: Value>(
+    input: &str,
+    name: &str,
+    (column, field): (usize, &str),
+) -> Result<V, Day07Error> {
+    field.parse::<V>().map_err(|parse_err| {
+        let error_msg = if !V::SIGNED && field.starts_with('-') {
+            "negative numbers need --signed".to_owned()
+        } else {
+            format!("could not parse '{field}' into number: {parse_err}")
         };
 
-        write!(f, "{v}")
-    }
+        Day07Error::EquationFieldParseError {
+            input: input.to_owned(),
+            field: name.to_owned(),
+            column,
+            error_msg,
+        }
+    })
 }
 
-pub(crate) struct Equation {
-    test_value: u64,
-    operands: Box<[u64]>,
+/// Operators assigned to equation slots, left to right
+pub(crate) struct Solution<'a, V> {
+    equation: &'a Equation<V>,
+    operators: Vec<&'a dyn Operator<V>>,
 }
 
-impl Equation {
-    fn new(test_value: u64, operands: Vec<u64>) -> Self {
-        let operands = operands.into_boxed_slice();
+impl<'a, V: Value> Solution<'a, V> {
+    fn new(equation: &'a Equation<V>, operators: Vec<&'a dyn Operator<V>>) -> Self {
+        debug_assert_eq!(equation.operands.len(), operators.len() + 1);
         Self {
-            test_value,
-            operands,
+            equation,
+            operators,
         }
     }
+}
 
-    pub(crate) fn is_solveable_with(&self, ops: &[Operator]) -> bool {
-        if self.operands.is_empty() {
-            return false;
-        } else if self.operands.len() == 1 {
-            return self.operands[0] == self.test_value;
+impl<V: Value> Display for Solution<'_, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some((first, rest)) = self.equation.operands.split_first() else {
+            return Ok(());
+        };
+
+        write!(f, "{first}")?;
+        for (op, operand) in self.operators.iter().zip(rest) {
+            write!(f, " {op} {operand}")?;
         }
 
-        let operator_slots = self.operands.len() - 1;
-        let combinations = ops.len().pow(operator_slots as u32);
+        Ok(())
+    }
+}
 
-        debug!("testing solveability for {testing_value} with {operand_count} operands and {operator_slots} operators with {combinations} combos", testing_value = self.test_value, operand_count = self.operands.len());
-        let mut operators_buf = vec![Operator::Add; operator_slots].into_boxed_slice();
+/// Depth first search from last operand to first, see `Equation::search`
+struct BackwardSearch<'a, V, F> {
+    ops: Vec<(&'a dyn Operator<V>, Inverter<V>)>,
+    /// Operators chosen so far, right to left
+    chosen: Vec<&'a dyn Operator<V>>,
+    /// Operators chosen left to right for operands whose value does not matter, see `run_defined`
+    prefix: Vec<&'a dyn Operator<V>>,
+    on_solution: F,
+}
 
-        for combination in 0..combinations {
-            make_combination_into(combination, ops, &mut operators_buf);
+impl<'a, V: Value, F: FnMut(Vec<&'a dyn Operator<V>>) -> ControlFlow<()>> BackwardSearch<'a, V, F> {
+    /// Tries to combine `operands` into `target`
+    fn run(&mut self, target: V, operands: &[V]) -> ControlFlow<()> {
+        let Some((&last, rest)) = operands.split_last() else {
+            return ControlFlow::Continue(());
+        };
 
-            if test_equation(&self.operands, &operators_buf, self.test_value) {
-                return true;
+        if rest.is_empty() {
+            if target != last {
+                return ControlFlow::Continue(());
             }
+
+            return self.solution();
         }
 
-        false
-    }
+        for op_idx in 0..self.ops.len() {
+            let (op, invert) = self.ops[op_idx];
+            let lhs = match invert(target, last) {
+                Inverse::Lhs(lhs) => Some(lhs),
+                Inverse::Any => None,
+                Inverse::Impossible => continue,
+            };
+
+            self.chosen.push(op);
+            let flow = match lhs {
+                Some(lhs) => self.run(lhs, rest),
+                None => {
+                    let (&first, rest) = rest.split_first().expect("checked to be non-empty");
+                    self.run_defined(first, rest)
+                }
+            };
+            self.chosen.pop();
+            flow?;
+        }
 
-    pub(crate) fn test_value(&self) -> u64 {
-        self.test_value
+        ControlFlow::Continue(())
     }
-}
-
-impl FromStr for Equation {
-    type Err = Day07Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some((test_val, operands)) = s.split_once(':') else {
-            return Err(Day07Error::EquationParseError {
-                input: s.to_owned(),
-                error_msg: "could not split equation into test value and operand list".to_owned(),
-            });
+    /// Any value of `lhs` combined with `operands` reaches target, e.g. when it is multiplied by
+    /// zero, but operators still have to be applicable. Tries them left to right from `lhs`.
+    fn run_defined(&mut self, lhs: V, operands: &[V]) -> ControlFlow<()> {
+        let Some((&next, rest)) = operands.split_first() else {
+            return self.solution();
         };
 
-        let test_val =
-            test_val
-                .parse::<u64>()
-                .map_err(|parse_err| Day07Error::EquationParseError {
-                    input: test_val.to_string(),
-                    error_msg: format!("could not parse test val into number: {parse_err}"),
-                })?;
-
-        let operands = operands
-            .trim()
-            .split(' ')
-            .map(|op| op.trim())
-            .map(|op| op.parse::<u64>())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|op_parse_err| Day07Error::EquationParseError {
-                input: operands.to_owned(),
-                error_msg: format!("failed to parse operand list: {op_parse_err}"),
-            })?;
+        for op_idx in 0..self.ops.len() {
+            let (op, _) = self.ops[op_idx];
+            let Some(value) = op.apply(lhs, next) else {
+                continue;
+            };
 
-        Ok(Self::new(test_val, operands))
+            self.prefix.push(op);
+            let flow = self.run_defined(value, rest);
+            self.prefix.pop();
+            flow?;
+        }
+
+        ControlFlow::Continue(())
     }
+
+    fn solution(&mut self) -> ControlFlow<()> {
+        let operators = self
+            .prefix
+            .iter()
+            .chain(self.chosen.iter().rev())
+            .copied()
+            .collect();
+
+        (self.on_solution)(operators)
+    }
+}
+
+/// Depth first search from first operand to last, see `Equation::search`
+struct ForwardSearch<'a, 'o, V, F> {
+    ops: &'o [&'a dyn Operator<V>],
+    evaluation: Evaluation,
+    test_value: V,
+    /// Operators chosen so far, left to right
+    chosen: Vec<&'a dyn Operator<V>>,
+    on_solution: F,
 }
 
-fn make_combination_into(mut combination_id: usize, op_choice: &[Operator], buf: &mut [Operator]) {
-    let max_id_allowed = (op_choice.len() as u32).pow(buf.len() as u32) as usize;
-    debug_assert!(combination_id < max_id_allowed, "combination id too large");
+impl<'a, V: Value, F: FnMut(Vec<&'a dyn Operator<V>>) -> ControlFlow<()>>
+    ForwardSearch<'a, '_, V, F>
+{
+    /// Continues expression whose operands before `current` wait in `pending` for operators
+    /// binding tighter on their right, `operands` are still to be placed
+    fn run(
+        &mut self,
+        pending: Vec<(V, &'a dyn Operator<V>)>,
+        current: V,
+        operands: &[V],
+    ) -> ControlFlow<()> {
+        let Some((&next, rest)) = operands.split_first() else {
+            let value = pending
+                .iter()
+                .rev()
+                .try_fold(current, |rhs, (lhs, op)| op.apply(*lhs, rhs));
+
+            if value == Some(self.test_value) {
+                return (self.on_solution)(self.chosen.clone());
+            }
+            return ControlFlow::Continue(());
+        };
 
-    for buf_idx in (0..buf.len()).rev() {
-        let choose_op = combination_id % op_choice.len();
-        combination_id /= op_choice.len();
+        for &op in self.ops {
+            let Some(pending) = self.reduce(pending.clone(), current, op) else {
+                continue;
+            };
 
-        buf[buf_idx] = op_choice[choose_op];
+            self.chosen.push(op);
+            let flow = self.run(pending, next, rest);
+            self.chosen.pop();
+            flow?;
+        }
+
+        ControlFlow::Continue(())
     }
-}
 
-fn test_equation(operands: &[u64], operators: &[Operator], test_value: u64) -> bool {
-    debug_assert!(
-        operands.len() - 1 == operators.len(),
-        "operands = {}, operators: {}, test_val = {}",
-        operands.len(),
-        operators.len(),
-        test_value
-    );
-
-    let val = operators
-        .iter()
-        .zip_eq(operands.iter().skip(1))
-        .fold_while(operands[0], |acc, (op, val)| {
-            let acc = op.apply(acc, *val);
-
-            if acc > test_value {
-                Done(acc)
-            } else {
-                Continue(acc)
+    /// Evaluates pending operators which have to be evaluated before `op`, innermost of them takes
+    /// `current` as right operand. Result becomes left operand of `op`. `None` if some operator
+    /// can not be applied.
+    fn reduce(
+        &self,
+        mut pending: Vec<(V, &'a dyn Operator<V>)>,
+        mut current: V,
+        op: &'a dyn Operator<V>,
+    ) -> Option<Vec<(V, &'a dyn Operator<V>)>> {
+        while let Some(&(lhs, top)) = pending.last() {
+            if !self.evaluated_before(top, op) {
+                break;
             }
-        })
-        .into_inner();
 
-    val == test_value
+            current = top.apply(lhs, current)?;
+            pending.pop();
+        }
+
+        pending.push((current, op));
+        Some(pending)
+    }
+
+    /// Whether `top` standing left of `op` is evaluated first. With precedence it binds tighter
+    /// or same and `op` is left associative, `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`.
+    fn evaluated_before(&self, top: &dyn Operator<V>, op: &dyn Operator<V>) -> bool {
+        match self.evaluation {
+            Evaluation::LeftToRight => true,
+            Evaluation::Precedence => match top.precedence().cmp(&op.precedence()) {
+                Ordering::Greater => true,
+                Ordering::Equal => !op.right_assoc(),
+                Ordering::Less => false,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::operators::{Add, Concatenation, Div, Mul, Pow, Sub};
+
+    fn rendered<V: Value>(solutions: Vec<Solution<'_, V>>) -> Vec<String> {
+        solutions.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn long_operand_list_is_solved() {
+        // 3^29 operator combinations, only feasible with pruning
+        let operands = [3, 7, 2, 9, 4, 5, 8, 6, 2, 3].repeat(3);
+        let equation = Equation::<u64>::new(381_810_284, operands);
+
+        assert!(equation.is_solveable_with(&[&Add, &Mul, &Concatenation], Evaluation::LeftToRight));
+        assert!(!equation.is_solveable_with(&[&Add, &Mul], Evaluation::LeftToRight));
+    }
+
+    #[test]
+    fn solutions_are_rendered_left_to_right() {
+        let equation = "3267: 81 40 27".parse::<Equation<u64>>().unwrap();
+
+        let solutions = equation.solutions(&[&Add, &Mul], Evaluation::LeftToRight);
+
+        assert_eq!(vec!["81 * 40 + 27", "81 + 40 * 27"], rendered(solutions));
+    }
+
+    #[test]
+    fn first_solution_stops_search() {
+        let equation = "7290: 6 8 6 15".parse::<Equation<u64>>().unwrap();
+
+        let solution = equation
+            .first_solution(&[&Add, &Mul, &Concatenation], Evaluation::LeftToRight)
+            .unwrap();
+
+        assert_eq!("6 * 8 || 6 * 15", solution.to_string());
+        assert!(equation
+            .first_solution(&[&Add, &Mul], Evaluation::LeftToRight)
+            .is_none());
+    }
+
+    #[test]
+    fn multiplication_by_zero_accepts_any_prefix() {
+        let equation = "0: 5 3 0".parse::<Equation<u64>>().unwrap();
+
+        let solutions = equation.solutions(&[&Add, &Mul], Evaluation::LeftToRight);
+
+        assert_eq!(vec!["5 + 3 * 0", "5 * 3 * 0"], rendered(solutions));
+    }
+
+    /// Hides inverse of wrapped operator, which forces left to right search
+    struct NoInverse(&'static dyn Operator<u64>);
+
+    impl Operator<u64> for NoInverse {
+        fn apply(&self, lhs: u64, rhs: u64) -> Option<u64> {
+            self.0.apply(lhs, rhs)
+        }
+
+        fn precedence(&self) -> u8 {
+            self.0.precedence()
+        }
+
+        fn right_assoc(&self) -> bool {
+            self.0.right_assoc()
+        }
+    }
+
+    impl Display for NoInverse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.fmt(f)
+        }
+    }
 
     #[test]
-    fn all_combinations_made() {
-        use Operator as Op;
-
-        let op_choice = [Op::Add, Op::Mul, Op::Concatenation];
-        let mut buf = vec![Op::Add; 2];
-
-        let results = [
-            [Op::Add, Op::Add],
-            [Op::Add, Op::Mul],
-            [Op::Add, Op::Concatenation],
-            [Op::Mul, Op::Add],
-            [Op::Mul, Op::Mul],
-            [Op::Mul, Op::Concatenation],
-            [Op::Concatenation, Op::Add],
-            [Op::Concatenation, Op::Mul],
-            [Op::Concatenation, Op::Concatenation],
-        ];
-
-        for id in 0..9 {
-            make_combination_into(id, &op_choice, &mut buf);
-            assert_eq!(results[id][..], buf[..]);
+    fn forward_and_backward_search_agree() {
+        let ops: [&dyn Operator<u64>; 4] = [&Add, &Mul, &Sub, &Div];
+        let forward_ops = ops.map(NoInverse);
+        let forward_ops = forward_ops
+            .iter()
+            .map(|op| op as &dyn Operator<u64>)
+            .collect::<Vec<_>>();
+
+        // multiplication by zero accepts any prefix, but `3 - 5` and `3 / 5` are not defined
+        for input in ["3267: 81 40 27", "0: 3 5 0", "0: 5 3 0", "0: 3 5 0 2"] {
+            let equation = input.parse::<Equation<u64>>().unwrap();
+
+            let mut backward = rendered(equation.solutions(&ops, Evaluation::LeftToRight));
+            let mut forward = rendered(equation.solutions(&forward_ops, Evaluation::LeftToRight));
+
+            backward.sort();
+            forward.sort();
+            assert_eq!(backward, forward, "{input}");
         }
+
+        let equation = "0: 3 5 0".parse::<Equation<u64>>().unwrap();
+        let solutions = equation.solutions(&[&Sub, &Mul], Evaluation::LeftToRight);
+        assert_eq!(vec!["3 * 5 * 0"], rendered(solutions));
+    }
+
+    #[test]
+    fn precedence_evaluation() {
+        let equation = "1161: 81 40 27".parse::<Equation<u64>>().unwrap();
+
+        // 81 + 40 * 27 = 81 + 1080, left to right it is 3267
+        let solutions = equation.solutions(&[&Add, &Mul], Evaluation::Precedence);
+        assert_eq!(vec!["81 + 40 * 27"], rendered(solutions));
+        assert!(!equation.is_solveable_with(&[&Add, &Mul], Evaluation::LeftToRight));
+
+        // 2 + 3 ^ 2 * 2 - 4 = 2 + 18 - 4
+        let equation = "16: 2 3 2 2 4".parse::<Equation<u64>>().unwrap();
+        let solutions = equation.solutions(&[&Add, &Sub, &Mul, &Pow], Evaluation::Precedence);
+        assert!(rendered(solutions).contains(&"2 + 3 ^ 2 * 2 - 4".to_owned()));
+
+        // exponentiation groups right to left, 2 ^ (3 ^ 2) = 2 ^ 9
+        let equation = "512: 2 3 2".parse::<Equation<u64>>().unwrap();
+        let solutions = equation.solutions(&[&Pow], Evaluation::Precedence);
+        assert_eq!(vec!["2 ^ 3 ^ 2"], rendered(solutions));
+
+        let equation = "64: 2 3 2".parse::<Equation<u64>>().unwrap();
+        assert!(!equation.is_solveable_with(&[&Pow], Evaluation::Precedence));
+        assert!(equation.is_solveable_with(&[&Pow], Evaluation::LeftToRight));
+    }
+
+    #[test]
+    fn overflowing_branches_are_not_solutions() {
+        // 2^32 * 2^32 wraps to 0 in unchecked u64 arithmetic
+        let equation = "0: 4294967296 4294967296".parse::<Equation<u64>>().unwrap();
+
+        assert!(!equation.is_solveable_with(&[&Add, &Mul], Evaluation::LeftToRight));
+        assert!(!equation.is_solveable_with(&[&Add, &Mul, &Pow], Evaluation::LeftToRight));
+
+        // multiplication by zero accepts any prefix, but it still must not overflow
+        let input = "0: 18446744073709551615 2 0";
+        let equation = input.parse::<Equation<u64>>().unwrap();
+        assert!(equation
+            .solutions(&[&Add, &Mul], Evaluation::LeftToRight)
+            .is_empty());
+
+        let equation = input.parse::<Equation<u128>>().unwrap();
+        assert_eq!(
+            2,
+            equation
+                .solutions(&[&Add, &Mul], Evaluation::LeftToRight)
+                .len()
+        );
+    }
+
+    #[test]
+    fn wide_values_fit_oversized_equations() {
+        let input = "18446744073709551616: 4294967296 4294967296";
+        assert!(input.parse::<Equation<u64>>().is_err());
+
+        let equation = input.parse::<Equation<u128>>().unwrap();
+        let solutions = equation.solutions(&[&Add, &Mul], Evaluation::LeftToRight);
+
+        assert_eq!(vec!["4294967296 * 4294967296"], rendered(solutions));
+    }
+
+    #[test]
+    fn equations_are_parsed_with_any_whitespace() {
+        for input in [
+            "190: 10 19",
+            "190:10\t19",
+            "  190 :  10   19  ",
+            "+190: 10 +19",
+        ] {
+            let equation = input.parse::<Equation<u64>>().unwrap();
+
+            assert_eq!(190, equation.test_value());
+            assert_eq!([10, 19][..], *equation.operands);
+        }
+    }
+
+    #[test]
+    fn signed_equations() {
+        let equation = "-6:\t4  -10".parse::<Equation<i64>>().unwrap();
+
+        let solutions = equation.solutions(&[&Add, &Mul], Evaluation::LeftToRight);
+
+        assert_eq!(vec!["4 + -10"], rendered(solutions));
+    }
+
+    #[test]
+    fn parse_errors_report_field_and_column() {
+        let field_error = |input: &str| match input.parse::<Equation<u64>>() {
+            Err(Day07Error::EquationFieldParseError {
+                field,
+                column,
+                error_msg,
+                ..
+            }) => (field, column, error_msg),
+            other => panic!("expected field error, got {:?}", other.err()),
+        };
+
+        let (field, column, _) = field_error("190: 10  x9");
+        assert_eq!(("operand 2".to_owned(), 10), (field, column));
+
+        let (field, column, _) = field_error("19a: 10 19");
+        assert_eq!(("test value".to_owned(), 1), (field, column));
+
+        let (field, column, error_msg) = field_error("5:\t-3 8");
+        assert_eq!(("operand 1".to_owned(), 4), (field, column));
+        assert!(error_msg.contains("--signed"));
+
+        let (_, column, _) = field_error("19 0: 10 9");
+        assert_eq!(4, column);
+
+        assert!(matches!(
+            "190:  ".parse::<Equation<u64>>(),
+            Err(Day07Error::EquationParseError { .. })
+        ));
     }
 }
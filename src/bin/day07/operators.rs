@@ -0,0 +1,334 @@
+use std::fmt::Display;
+
+use crate::error::Day07Error;
+use crate::value::Value;
+
+/// Left hand side operand which gives `result` with given right hand side operand
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Inverse<V> {
+    /// There is exactly one such operand
+    Lhs(V),
+    /// Every operand gives `result`, e.g. multiplication by zero
+    Any,
+    /// There is no such operand, also when it would not fit into `V`
+    Impossible,
+}
+
+/// Finds `lhs` for which `lhs <op> rhs == result`
+pub(crate) type Inverter<V> = fn(result: V, rhs: V) -> Inverse<V>;
+
+/// Binary operator which can be placed between equation operands, symbol is its `Display`
+pub(crate) trait Operator<V: Value>: Display + Sync {
+    /// `None` if result is not defined, e.g. division by zero or result out of range
+    fn apply(&self, lhs: V, rhs: V) -> Option<V>;
+
+    /// Inverse used to search equations right to left, without one operator is only tried left
+    /// to right
+    fn inverse(&self) -> Option<Inverter<V>> {
+        None
+    }
+
+    /// How tightly operator binds when evaluated with standard precedence, higher binds tighter
+    fn precedence(&self) -> u8;
+
+    /// Whether chain of operators with same precedence groups right to left when evaluated with
+    /// standard precedence
+    fn right_assoc(&self) -> bool {
+        false
+    }
+}
+
+/// Every known operator, new operator needs its type and entry here
+pub(crate) fn operators<V: Value>() -> [&'static dyn Operator<V>; 6] {
+    [&Add, &Mul, &Concatenation, &Sub, &Div, &Pow]
+}
+
+/// Parses comma separated operator symbols, e.g. `+,*,||`
+pub(crate) fn parse_operators<V: Value>(
+    symbols: &str,
+) -> Result<Vec<&'static dyn Operator<V>>, Day07Error> {
+    symbols
+        .split(',')
+        .map(str::trim)
+        .map(|symbol| {
+            operators()
+                .into_iter()
+                .find(|op| op.to_string() == symbol)
+                .ok_or_else(|| Day07Error::UnknownOperatorError {
+                    symbol: symbol.to_owned(),
+                })
+        })
+        .collect()
+}
+
+pub(crate) struct Add;
+
+impl<V: Value> Operator<V> for Add {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        lhs.checked_add(rhs)
+    }
+
+    fn inverse(&self) -> Option<Inverter<V>> {
+        Some(|result, rhs| {
+            result
+                .checked_sub(rhs)
+                .map_or(Inverse::Impossible, Inverse::Lhs)
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+}
+
+impl Display for Add {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+")
+    }
+}
+
+pub(crate) struct Mul;
+
+impl<V: Value> Operator<V> for Mul {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        lhs.checked_mul(rhs)
+    }
+
+    fn inverse(&self) -> Option<Inverter<V>> {
+        Some(|result, rhs| {
+            if result == V::ZERO && rhs == V::ZERO {
+                return Inverse::Any;
+            }
+
+            result
+                .exact_div(rhs)
+                .map_or(Inverse::Impossible, Inverse::Lhs)
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+}
+
+impl Display for Mul {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "*")
+    }
+}
+
+/// Appends digits of right operand to left one, `15 || 6 = 156`. Defined only for non-negative
+/// operands.
+pub(crate) struct Concatenation;
+
+impl<V: Value> Operator<V> for Concatenation {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        if lhs < V::ZERO {
+            return None;
+        }
+
+        lhs.checked_mul(digits_shift(rhs)?)?.checked_add(rhs)
+    }
+
+    fn inverse(&self) -> Option<Inverter<V>> {
+        // `rhs` is below shift, so `result` ends with its digits exactly when `result - rhs` is
+        // multiple of shift
+        Some(|result, rhs| {
+            digits_shift(rhs)
+                .and_then(|shift| result.checked_sub(rhs)?.exact_div(shift))
+                .filter(|lhs| *lhs >= V::ZERO)
+                .map_or(Inverse::Impossible, Inverse::Lhs)
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        0
+    }
+}
+
+impl Display for Concatenation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "||")
+    }
+}
+
+/// Power of ten with as many zeros as `num` has digits, `num` is shifted by it when concatenated.
+/// `None` if it does not fit into `V` or `num` is negative.
+fn digits_shift<V: Value>(num: V) -> Option<V> {
+    if num < V::ZERO {
+        return None;
+    }
+
+    let mut shift = V::TEN;
+    while shift <= num {
+        shift = shift.checked_mul(V::TEN)?;
+    }
+
+    Some(shift)
+}
+
+/// Subtraction, for unsigned values result must not go below zero
+pub(crate) struct Sub;
+
+impl<V: Value> Operator<V> for Sub {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        lhs.checked_sub(rhs)
+    }
+
+    fn inverse(&self) -> Option<Inverter<V>> {
+        Some(|result, rhs| {
+            result
+                .checked_add(rhs)
+                .map_or(Inverse::Impossible, Inverse::Lhs)
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+}
+
+impl Display for Sub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "-")
+    }
+}
+
+/// Exact division, defined only when right operand divides left one
+pub(crate) struct Div;
+
+impl<V: Value> Operator<V> for Div {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        lhs.exact_div(rhs)
+    }
+
+    fn inverse(&self) -> Option<Inverter<V>> {
+        Some(|result, rhs| {
+            if rhs == V::ZERO {
+                return Inverse::Impossible;
+            }
+
+            result
+                .checked_mul(rhs)
+                .map_or(Inverse::Impossible, Inverse::Lhs)
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+}
+
+impl Display for Div {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/")
+    }
+}
+
+/// Exponentiation, has no inverse so equations using it are searched left to right
+pub(crate) struct Pow;
+
+impl<V: Value> Operator<V> for Pow {
+    fn apply(&self, lhs: V, rhs: V) -> Option<V> {
+        lhs.checked_pow(rhs)
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn right_assoc(&self) -> bool {
+        true
+    }
+}
+
+impl Display for Pow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "^")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invert(op: &dyn Operator<u64>, result: u64, rhs: u64) -> Inverse<u64> {
+        op.inverse().expect("operator has inverse")(result, rhs)
+    }
+
+    #[test]
+    fn inverts_operators() {
+        assert_eq!(Inverse::Lhs(81), invert(&Add, 121, 40));
+        assert_eq!(Inverse::Impossible, invert(&Add, 30, 40));
+        assert_eq!(Inverse::Lhs(3), invert(&Mul, 12, 4));
+        assert_eq!(Inverse::Impossible, invert(&Mul, 13, 4));
+        assert_eq!(Inverse::Any, invert(&Mul, 0, 0));
+        assert_eq!(Inverse::Impossible, invert(&Mul, 5, 0));
+        assert_eq!(Inverse::Lhs(15), invert(&Concatenation, 156, 6));
+        assert_eq!(Inverse::Lhs(1), invert(&Concatenation, 10, 0));
+        assert_eq!(Inverse::Impossible, invert(&Concatenation, 156, 16));
+        assert_eq!(Inverse::Impossible, invert(&Concatenation, 6, 16));
+        assert_eq!(Inverse::Lhs(12), invert(&Sub, 8, 4));
+        assert_eq!(Inverse::Lhs(12), invert(&Div, 3, 4));
+        assert!(Operator::<u64>::inverse(&Pow).is_none());
+    }
+
+    #[test]
+    fn applies_operators() {
+        assert_eq!(Some(156), Concatenation.apply(15u64, 6));
+        assert_eq!(Some(1510), Concatenation.apply(15u64, 10));
+        assert_eq!(Some(150), Concatenation.apply(15u64, 0));
+        assert_eq!(None, Sub.apply(3u64, 4));
+        assert_eq!(None, Div.apply(7u64, 2));
+        assert_eq!(None, Div.apply(7u64, 0));
+        assert_eq!(Some(81), Pow.apply(3u64, 4));
+    }
+
+    #[test]
+    fn overflow_is_not_a_result() {
+        assert_eq!(None, Add.apply(u64::MAX, 1));
+        assert_eq!(None, Mul.apply(u64::MAX / 2, 3));
+        assert_eq!(None, Concatenation.apply(u64::MAX / 10, 99));
+        assert_eq!(None, Concatenation.apply(1, u64::MAX));
+        assert_eq!(None, Pow.apply(2u64, 64));
+        assert_eq!(Inverse::Impossible, invert(&Sub, u64::MAX, 1));
+        assert_eq!(Inverse::Impossible, invert(&Div, u64::MAX, 2));
+
+        // same operations fit into wide values
+        assert_eq!(Some(1 << 64), Pow.apply(2u128, 64));
+        assert_eq!(
+            Some(u128::from(u64::MAX) * 100 + 99),
+            Concatenation.apply(u128::from(u64::MAX), 99)
+        );
+    }
+
+    #[test]
+    fn signed_operators() {
+        assert_eq!(Some(-6), Add.apply(4i64, -10));
+        assert_eq!(Some(-40), Mul.apply(-4i64, 10));
+        assert_eq!(Some(-3), Div.apply(-12i64, 4));
+        assert_eq!(None, Div.apply(i64::MIN, -1));
+        assert_eq!(None, Pow.apply(2i64, -1));
+        assert_eq!(None, Concatenation.apply(-15i64, 6));
+        assert_eq!(None, Concatenation.apply(15i64, -6));
+
+        let invert = |op: &dyn Operator<i64>, result, rhs| op.inverse().unwrap()(result, rhs);
+        assert_eq!(Inverse::Lhs(-3), invert(&Mul, -12, 4));
+        assert_eq!(Inverse::Lhs(-6), invert(&Sub, 4, -10));
+        assert_eq!(Inverse::Impossible, invert(&Concatenation, -4, 6));
+    }
+
+    #[test]
+    fn operators_are_parsed_by_symbol() {
+        let ops = parse_operators::<u64>("+, ||,^").unwrap();
+
+        assert_eq!(
+            vec!["+", "||", "^"],
+            ops.iter().map(|op| op.to_string()).collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            parse_operators::<u64>("+,%"),
+            Err(Day07Error::UnknownOperatorError { .. })
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt::{Debug, Display};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// Integer type equations are evaluated in. Every operation is checked, `None` means result does
+/// not fit and branch using it is not a solution.
+pub(crate) trait Value:
+    Copy + Eq + Ord + Debug + Display + FromStr<Err = ParseIntError> + Send + Sync + 'static
+{
+    const ZERO: Self;
+    const TEN: Self;
+    /// Whether negative values can be represented
+    const SIGNED: bool;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    /// `None` also when `rhs` is zero or does not divide `self`
+    fn exact_div(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: Self) -> Option<Self>;
+}
+
+macro_rules! impl_value {
+    ($($ty:ty),*) => {$(
+        impl Value for $ty {
+            const ZERO: Self = 0;
+            const TEN: Self = 10;
+            const SIGNED: bool = <$ty>::MIN != 0;
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_add(self, rhs)
+            }
+
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_sub(self, rhs)
+            }
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$ty>::checked_mul(self, rhs)
+            }
+
+            fn exact_div(self, rhs: Self) -> Option<Self> {
+                self.checked_rem(rhs)
+                    .filter(|rem| *rem == 0)
+                    .and_then(|_| self.checked_div(rhs))
+            }
+
+            fn checked_pow(self, exp: Self) -> Option<Self> {
+                <$ty>::checked_pow(self, u32::try_from(exp).ok()?)
+            }
+        }
+    )*};
+}
+
+impl_value!(u64, u128, i64, i128);
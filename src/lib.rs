@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{fs::File, path::PathBuf};
 
 use eyre::Context;
 use tracing_subscriber::EnvFilter;
@@ -23,15 +23,43 @@ pub fn init() {
 }
 
 pub fn load_day_input(day_name: impl AsRef<str>) -> eyre::Result<String> {
-    const INPUTS_DIR_NAME: &str = "inputs";
     let day_name = day_name.as_ref();
-
-    let input_path: PathBuf = [INPUTS_DIR_NAME, day_name].iter().collect();
-    let input_path = input_path
-        .canonicalize()
-        .wrap_err_with(|| format!("failed to expand input file path: {:?}", input_path))?;
+    let input_path = day_input_path(day_name)?;
 
     std::fs::read_to_string(input_path.as_path()).wrap_err_with(|| {
         format!("failed to load input data for {day_name} from path {input_path:?}")
     })
 }
+
+/// Opens input file without reading it, for days which process input as a stream
+pub fn open_day_input(day_name: impl AsRef<str>) -> eyre::Result<File> {
+    let day_name = day_name.as_ref();
+    let input_path = day_input_path(day_name)?;
+
+    File::open(input_path.as_path()).wrap_err_with(|| {
+        format!("failed to open input data for {day_name} from path {input_path:?}")
+    })
+}
+
+fn day_input_path(day_name: &str) -> eyre::Result<PathBuf> {
+    const INPUTS_DIR_NAME: &str = "inputs";
+
+    let input_path: PathBuf = [INPUTS_DIR_NAME, day_name].iter().collect();
+    input_path
+        .canonicalize()
+        .wrap_err_with(|| format!("failed to expand input file path: {:?}", input_path))
+}
+
+/// Checks if `flag` was passed as command line argument to the running executable
+pub fn has_cli_flag(flag: impl AsRef<str>) -> bool {
+    let flag = flag.as_ref();
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Value of command line argument given as `flag=value`, e.g. `--stencil=plus.txt`
+pub fn cli_flag_value(flag: impl AsRef<str>) -> Option<String> {
+    let prefix = format!("{}=", flag.as_ref());
+    std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.strip_prefix(&prefix).map(str::to_owned))
+}